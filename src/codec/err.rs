@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+use crate::de::BencodingDeserializeError;
+use crate::ser::BencodingSerializeError;
+
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("an error occurred while reading or writing the stream: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("an error occurred while decoding a value: {0}")]
+    Deserialize(#[from] BencodingDeserializeError),
+    #[error("an error occurred while encoding a value: {0}")]
+    Serialize(#[from] BencodingSerializeError),
+    #[error("a value is longer than the limit of {0} bytes")]
+    TooLong(usize),
+}
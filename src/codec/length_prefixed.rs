@@ -0,0 +1,155 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+use bytes::{Buf, BufMut, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{CodecError, DEFAULT_MAX_LENGTH};
+use crate::{from_bytes_with_options, to_canonical_writer, DecoderOptions};
+
+/// The length of the prefix before each value
+const PREFIX_LEN: usize = 4;
+
+/// Decodes a stream of bencoded values that are each prefixed with their
+/// length, as a 4 byte big-endian integer, and encodes values the same way
+///
+/// The bytes after the prefix must be exactly one value.
+///
+/// ```
+/// use bencoding::codec::LengthPrefixedCodec;
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = LengthPrefixedCodec::<Vec<u32>>::new();
+/// let mut buffer = BytesMut::new();
+///
+/// codec.encode(vec![1, 2], &mut buffer).unwrap();
+/// assert_eq!(&b"\x00\x00\x00\x08li1ei2ee"[..], &buffer[..]);
+/// assert_eq!(Some(vec![1, 2]), codec.decode(&mut buffer).unwrap());
+/// ```
+pub struct LengthPrefixedCodec<T> {
+    max_length: usize,
+    options: DecoderOptions,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<T> LengthPrefixedCodec<T> {
+    /// Creates a codec that allows values of up to [`DEFAULT_MAX_LENGTH`]
+    /// bytes, not counting the prefix
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_max_length(DEFAULT_MAX_LENGTH)
+    }
+
+    /// Creates a codec that allows values of up to the given length, not
+    /// counting the prefix, so a peer can't make it buffer without limit
+    #[must_use]
+    pub const fn with_max_length(max_length: usize) -> Self {
+        Self {
+            max_length,
+            options: DecoderOptions::new(),
+            output: PhantomData,
+        }
+    }
+
+    /// Sets the options values are decoded with, such as strict decoding or
+    /// a different limit on nesting
+    #[must_use]
+    pub const fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    #[must_use]
+    pub const fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    #[must_use]
+    pub const fn options(&self) -> DecoderOptions {
+        self.options
+    }
+}
+
+impl<T> Default for LengthPrefixedCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implemented by hand, as deriving would require `T` to implement them
+impl<T> Clone for LengthPrefixedCodec<T> {
+    fn clone(&self) -> Self {
+        Self::with_max_length(self.max_length).with_options(self.options)
+    }
+}
+
+impl<T> fmt::Debug for LengthPrefixedCodec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LengthPrefixedCodec")
+            .field("max_length", &self.max_length)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<T> Decoder for LengthPrefixedCodec<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = T;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, CodecError> {
+        let Some(prefix) = src.get(..PREFIX_LEN) else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]);
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= self.max_length)
+            .ok_or(CodecError::TooLong(self.max_length))?;
+
+        if src.len() < PREFIX_LEN + len {
+            // Make room for the rest of the value, now that its length is
+            // known to be allowed
+            src.reserve(PREFIX_LEN + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(PREFIX_LEN);
+        let value = src.split_to(len);
+        Ok(Some(from_bytes_with_options(&value, self.options)?))
+    }
+}
+
+impl<T, U> Encoder<U> for LengthPrefixedCodec<T>
+where
+    U: Serialize,
+{
+    type Error = CodecError;
+
+    fn encode(&mut self, item: U, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let start = dst.len();
+        dst.put_u32(0);
+        to_canonical_writer(dst.writer(), &item).map_err(|e| {
+            dst.truncate(start);
+            e
+        })?;
+
+        let len = dst.len() - start - PREFIX_LEN;
+        match u32::try_from(len) {
+            Ok(prefix) if len <= self.max_length => {
+                dst[start..start + PREFIX_LEN].copy_from_slice(&prefix.to_be_bytes());
+                Ok(())
+            }
+            _ => {
+                dst.truncate(start);
+                Err(CodecError::TooLong(self.max_length))
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Framing of bencoded values on async streams, for use with
+//! [`tokio_util::codec::Framed`] and friends.
+//!
+//! Bencoded values are self-delimiting, so no length prefix is needed. With
+//! a [`BencodeCodec`], a value is decoded once all of its bytes have arrived,
+//! and the bytes after it are left for the next one. They are scanned as they
+//! arrive, carrying on from where the last call stopped.
+//!
+//! For a protocol that does prefix messages with their length, a
+//! [`LengthPrefixedCodec`] frames each value with a 4 byte big-endian length
+//! instead.
+//!
+//! Both codecs decode values according to their [`DecoderOptions`], and
+//! encode them canonically.
+//!
+//! This module is only available with the `tokio-codec` feature.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use bytes::{BufMut, BytesMut};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::de::BencodingDeserializeError;
+use crate::{from_bytes_prefix, from_bytes_with_options, to_canonical_writer, DecoderOptions};
+
+mod err;
+mod length_prefixed;
+
+pub use err::CodecError;
+pub use length_prefixed::LengthPrefixedCodec;
+
+/// The default limit on the length of a value, in bytes, for both codecs
+pub const DEFAULT_MAX_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Decodes a stream of bytes into consecutive bencoded values of type `T`,
+/// and encodes values canonically
+///
+/// ```
+/// use bencoding::codec::BencodeCodec;
+/// use bytes::BytesMut;
+/// use tokio_util::codec::Decoder;
+///
+/// let mut codec = BencodeCodec::<Vec<u32>>::new();
+/// let mut buffer = BytesMut::from(&b"li1ei2"[..]);
+///
+/// // Nothing is decoded until the whole value has arrived
+/// assert_eq!(None, codec.decode(&mut buffer).unwrap());
+///
+/// buffer.extend_from_slice(b"eeli3ee");
+/// assert_eq!(Some(vec![1, 2]), codec.decode(&mut buffer).unwrap());
+/// assert_eq!(Some(vec![3]), codec.decode(&mut buffer).unwrap());
+/// ```
+pub struct BencodeCodec<T> {
+    max_length: usize,
+    options: DecoderOptions,
+    scan: Scan,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<T> BencodeCodec<T> {
+    /// Creates a codec that allows values of up to [`DEFAULT_MAX_LENGTH`]
+    /// bytes
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_max_length(DEFAULT_MAX_LENGTH)
+    }
+
+    /// Creates a codec that allows values of up to the given length, so a
+    /// peer can't make it buffer without limit
+    #[must_use]
+    pub const fn with_max_length(max_length: usize) -> Self {
+        Self {
+            max_length,
+            options: DecoderOptions::new(),
+            scan: Scan::new(),
+            output: PhantomData,
+        }
+    }
+
+    /// Sets the options values are decoded with, such as strict decoding or
+    /// a different limit on nesting, as [`from_bytes_with_options`] takes
+    #[must_use]
+    pub const fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    #[must_use]
+    pub const fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    #[must_use]
+    pub const fn options(&self) -> DecoderOptions {
+        self.options
+    }
+}
+
+impl<T> Default for BencodeCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implemented by hand, as deriving would require `T` to implement them
+impl<T> Clone for BencodeCodec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            max_length: self.max_length,
+            options: self.options,
+            scan: self.scan.clone(),
+            output: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for BencodeCodec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BencodeCodec")
+            .field("max_length", &self.max_length)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Decoder for BencodeCodec<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = T;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, CodecError> {
+        // Find where the value ends first, so that a value that isn't a valid
+        // `T` is still consumed, and the stream can carry on after it
+        let len = match self.scan.frame_len(src, self.options.max_depth) {
+            Ok(Some(len)) => len,
+            Ok(None) if src.len() > self.max_length => {
+                return Err(CodecError::TooLong(self.max_length))
+            }
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                self.scan = Scan::new();
+                return Err(e.into());
+            }
+        };
+        self.scan = Scan::new();
+        if len > self.max_length {
+            return Err(CodecError::TooLong(self.max_length));
+        }
+
+        let value = src.split_to(len);
+        Ok(Some(from_bytes_with_options(&value, self.options)?))
+    }
+}
+
+// How much of the next value has been scanned, so that scanning carries on
+// from there once more bytes arrive, rather than starting over
+#[derive(Debug, Clone)]
+struct Scan {
+    // The index just past the last whole token
+    offset: usize,
+    // The lists and dictionaries that the offset is inside of
+    open: Vec<Container>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    List,
+    // A dictionary whose next token is a key, or the `e` ending it
+    DictKey,
+    // A dictionary whose next token is a value
+    DictValue,
+}
+
+impl Scan {
+    const fn new() -> Self {
+        Self {
+            offset: 0,
+            open: Vec::new(),
+        }
+    }
+
+    // Scans the tokens that have arrived, returning the length of the value
+    // once it's whole. Lists and dictionaries are kept track of here rather
+    // than by recursing, and only strings and integers are decoded.
+    fn frame_len(
+        &mut self,
+        src: &[u8],
+        max_depth: usize,
+    ) -> Result<Option<usize>, BencodingDeserializeError> {
+        while let Some(&byte) = src.get(self.offset) {
+            match (byte, self.open.last()) {
+                (b'e', Some(Container::List | Container::DictKey)) => {
+                    self.open.pop();
+                    self.offset += 1;
+                }
+                (b'l' | b'd', None | Some(Container::List | Container::DictValue)) => {
+                    if self.open.len() >= max_depth {
+                        return Err(BencodingDeserializeError::DepthLimitExceeded(max_depth));
+                    }
+                    self.open.push(if byte == b'l' {
+                        Container::List
+                    } else {
+                        Container::DictKey
+                    });
+                    self.offset += 1;
+                    continue;
+                }
+                (_, open) => {
+                    let rest = &src[self.offset..];
+                    let token = if matches!(open, Some(Container::DictKey)) {
+                        from_bytes_prefix::<&[u8]>(rest).map(|(_, len)| len)
+                    } else {
+                        from_bytes_prefix::<IgnoredAny>(rest).map(|(_, len)| len)
+                    };
+
+                    match token {
+                        Ok(len) => self.offset += len,
+                        // The rest of the token hasn't arrived yet
+                        Err(e)
+                            if matches!(e.kind(), BencodingDeserializeError::OutOfCharacters) =>
+                        {
+                            return Ok(None)
+                        }
+                        Err(e) => return Err(e.into_kind()),
+                    }
+                }
+            }
+
+            // A whole value has been scanned
+            match self.open.last_mut() {
+                None => return Ok(Some(self.offset)),
+                Some(open @ Container::DictKey) => *open = Container::DictValue,
+                Some(open @ Container::DictValue) => *open = Container::DictKey,
+                Some(Container::List) => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<T, U> Encoder<U> for BencodeCodec<T>
+where
+    U: Serialize,
+{
+    type Error = CodecError;
+
+    fn encode(&mut self, item: U, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let start = dst.len();
+        to_canonical_writer(dst.writer(), &item)?;
+
+        if dst.len() - start > self.max_length {
+            dst.truncate(start);
+            return Err(CodecError::TooLong(self.max_length));
+        }
+
+        Ok(())
+    }
+}
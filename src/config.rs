@@ -0,0 +1,82 @@
+//! Encodings for types that bencode has no equivalent for.
+//!
+//! Bencode has no booleans, null or unit, and dictionary keys can only be byte
+//! strings, so how those types are encoded is chosen with a [`Config`], which
+//! should be the same when serializing and deserializing a value.
+
+/// How types that bencode has no equivalent for are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// How booleans are encoded
+    pub bool_encoding: BoolEncoding,
+    /// How `None` is encoded
+    pub none_encoding: NoneEncoding,
+    /// How unit and unit structs are encoded
+    pub unit_encoding: UnitEncoding,
+    /// How integer dictionary keys are encoded
+    pub int_key_encoding: IntKeyEncoding,
+}
+
+impl Config {
+    /// The default config, under which booleans, unit and integer keys can't
+    /// be encoded, and `None` struct fields are left out
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bool_encoding: BoolEncoding::Error,
+            none_encoding: NoneEncoding::Omit,
+            unit_encoding: UnitEncoding::Error,
+            int_key_encoding: IntKeyEncoding::Error,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How booleans are encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoolEncoding {
+    /// Booleans can't be serialized or deserialized
+    #[default]
+    Error,
+    /// `false` is encoded as `i0e` and `true` as `i1e`, as many clients do
+    Int,
+}
+
+/// How `None` is encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoneEncoding {
+    /// A dictionary entry or struct field whose value is `None` is left out
+    /// entirely, and a missing field is deserialized as `None`. `None`
+    /// anywhere else, such as in a list, can't be serialized.
+    #[default]
+    Omit,
+    /// `None` can't be serialized
+    Error,
+}
+
+/// How unit, such as `()`, and unit structs are encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnitEncoding {
+    /// Unit can't be serialized or deserialized
+    #[default]
+    Error,
+    /// Unit is encoded as the empty byte string `0:`
+    EmptyString,
+}
+
+/// How integer dictionary keys, such as those of a `HashMap<u32, _>`, are
+/// encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntKeyEncoding {
+    /// Integer keys can't be serialized or deserialized
+    #[default]
+    Error,
+    /// Integer keys are encoded as their decimal digits, so `5` is the key
+    /// `1:5`, as `serde_json` does
+    String,
+}
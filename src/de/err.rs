@@ -4,6 +4,8 @@ use thiserror::Error;
 pub enum BencodingDeserializeError {
     #[error("{0}")]
     SerdeDeserializeError(String),
+    #[error("an error occurred while reading the input: {0}")]
+    Io(std::io::Error),
     #[error("a character was tried to be read with none remaining")]
     OutOfCharacters,
     #[error("after parsing there are unexpected characters remaining")]
@@ -18,14 +20,88 @@ pub enum BencodingDeserializeError {
     InputNotUtf8,
     #[error("an error occurred while parsing an int")]
     ParseIntError,
+    #[error("an integer or string length has no digits")]
+    EmptyInteger,
+    #[error("an integer or string length has a leading zero")]
+    LeadingZero,
+    #[error("the integer '-0' is not allowed")]
+    NegativeZero,
+    #[error("dictionary key '{0}' is not sorted after the previous key")]
+    UnsortedKey(String),
+    #[error("dictionary key '{0}' appears more than once")]
+    DuplicateKey(String),
     #[error("type 'bool' not supported")]
     InvalidTypeBool,
     #[error("type 'float' not supported")]
     InvalidTypeFloat,
+    #[error("type 'unit' not supported")]
+    InvalidTypeUnit,
     #[error("type '{0}' not supported")]
     InvalidTypeOther(char),
-    #[error("expected null")]
+    #[error("expected unit, encoded as an empty byte string")]
     ExpectedNull,
+    #[error("lists and dictionaries are nested more than {0} levels deep")]
+    DepthLimitExceeded(usize),
+    #[error("{error} at byte {offset}{}, near `{snippet}`", display_path(.path))]
+    Located {
+        error: Box<Self>,
+        // The byte offset into the input at which the error occurred
+        offset: usize,
+        // The dictionary keys and list positions leading to the value being
+        // read, such as `info.files[3].length`
+        path: String,
+        // The bytes around the offset, with non-printable bytes escaped
+        snippet: String,
+    },
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" in `{path}`")
+    }
+}
+
+impl BencodingDeserializeError {
+    /// Returns the underlying error, without any location information
+    #[must_use]
+    pub fn kind(&self) -> &Self {
+        match self {
+            Self::Located { error, .. } => error,
+            error => error,
+        }
+    }
+
+    /// Converts the error into the underlying error, discarding any location
+    /// information
+    #[must_use]
+    pub fn into_kind(self) -> Self {
+        match self {
+            Self::Located { error, .. } => *error,
+            error => error,
+        }
+    }
+
+    /// Returns the byte offset into the input at which the error occurred, if
+    /// it's known
+    #[must_use]
+    pub const fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Located { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the path to the value that was being read when the error
+    /// occurred, if it's known
+    #[must_use]
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Located { path, .. } => Some(path),
+            _ => None,
+        }
+    }
 }
 
 impl serde::de::Error for BencodingDeserializeError {
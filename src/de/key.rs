@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+
+use super::{BencodingDeserializeError, Deserializer, Result};
+use crate::config::IntKeyEncoding;
+
+// Deserializes a dictionary key. Keys are always byte strings, so when the
+// config allows integer keys they are parsed from the key's digits, mirroring
+// how `MapKeySerializer` writes them. Everything else is read as usual.
+pub struct MapKeyDeserializer<'a, 'de> {
+    pub de: &'a mut Deserializer<'de>,
+}
+
+impl MapKeyDeserializer<'_, '_> {
+    // Only the digits `MapKeySerializer` writes are accepted, so that each
+    // integer has exactly one key: an optional `-` followed by digits, with no
+    // leading zeros and no `-0`
+    fn parse_integer<T>(self) -> Result<T>
+    where
+        T: FromStr,
+    {
+        let range = self.de.parse_bytes()?;
+        let key = &self.de.input.bytes()[range];
+        let digits = key.strip_prefix(b"-").unwrap_or(key);
+
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return Err(BencodingDeserializeError::ParseIntError);
+        }
+        if digits.len() > 1 && digits[0] == b'0' {
+            return Err(BencodingDeserializeError::LeadingZero);
+        }
+        if digits.len() < key.len() && digits == b"0" {
+            return Err(BencodingDeserializeError::NegativeZero);
+        }
+
+        std::str::from_utf8(key)
+            .ok()
+            .and_then(|key| key.parse().ok())
+            .ok_or(BencodingDeserializeError::ParseIntError)
+    }
+}
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                match self.de.config.int_key_encoding {
+                    IntKeyEncoding::Error => de::Deserializer::$method(self.de, visitor),
+                    IntKeyEncoding::String => visitor.$visit(self.parse_integer()?),
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! forward_to_deserializer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                de::Deserializer::$method(self.de, visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKeyDeserializer<'_, 'de> {
+    type Error = BencodingDeserializeError;
+
+    deserialize_integer_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
+    forward_to_deserializer! {
+        deserialize_any deserialize_bool deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_unit deserialize_seq deserialize_map
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    // Newtype keys are read as the key they wrap, so that a newtype around an
+    // integer is parsed the same way
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_unit_struct(self.de, name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple(self.de, len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple_struct(self.de, name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_struct(self.de, name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_enum(self.de, name, variants, visitor)
+    }
+}
@@ -1,20 +1,92 @@
 use num::{Num, PrimInt};
-use std::fmt::Display;
-use std::ops::Neg;
+use std::cmp::Ordering;
+use std::fmt::{Display, Write as _};
+use std::io;
+use std::ops::{Neg, Range};
 
-use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor};
 use serde::Deserialize;
 
+use crate::config::{BoolEncoding, Config, UnitEncoding};
+use crate::value::RAW_TOKEN;
+
 mod err;
+mod key;
+mod read;
+mod stream;
 
 pub use err::BencodingDeserializeError;
+use key::MapKeyDeserializer;
+use read::Input;
+pub use stream::StreamDeserializer;
 type Result<T> = std::result::Result<T, BencodingDeserializeError>;
 
+/// The default limit on how deeply lists and dictionaries may be nested
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Options controlling how strictly input is decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecoderOptions {
+    /// Enforces the grammar of BEP 3, so that every value has exactly one
+    /// valid encoding. Integers and string lengths with leading zeros, `-0`,
+    /// and dictionaries whose keys are unsorted or repeated are all rejected.
+    pub strict: bool,
+    /// How deeply lists and dictionaries may be nested. Each level is read
+    /// recursively, so this stops untrusted input from overflowing the stack.
+    pub max_depth: usize,
+}
+
+impl DecoderOptions {
+    /// The default options, which don't enforce the grammar of BEP 3, and
+    /// allow nesting up to [`DEFAULT_MAX_DEPTH`] levels deep
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            strict: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Options that enforce the grammar of BEP 3
+    #[must_use]
+    pub const fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for DecoderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct Deserializer<'de> {
     // The data being deserialized
-    input: &'de [u8],
+    input: Input<'de>,
     // The index of the next character to be read in input
     offset: usize,
+    // The number of bytes read and then discarded from the start of a
+    // reader's buffer, which isn't counted in offset
+    discarded: usize,
+    options: DecoderOptions,
+    config: Config,
+    // The number of lists and dictionaries the offset is inside of
+    depth: usize,
+    // The dictionary keys and list positions leading to the value currently
+    // being read. Segments are only popped once their value has been read
+    // successfully, so after an error this is the path to where it occurred.
+    path: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone)]
+enum PathSegment {
+    // The key's bytes, copied out of the input so that they outlive a
+    // reader's buffer
+    Key(Vec<u8>),
+    Index(usize),
 }
 
 impl<'de> Deserializer<'de> {
@@ -22,18 +94,174 @@ impl<'de> Deserializer<'de> {
     // That way basic use cases are satisfied by something like
     // `serde_json::from_str(...)` while advanced use cases that require a
     // deserializer can make one with `serde_json::Deserializer::from_str(...)`.
+    #[must_use]
     pub const fn from_str(input: &'de str) -> Self {
+        Self::from_bytes(input.as_bytes())
+    }
+
+    #[must_use]
+    pub const fn from_bytes(input: &'de [u8]) -> Self {
+        Self::with_options(input, DecoderOptions::new())
+    }
+
+    /// Creates a deserializer that decodes according to the given options
+    #[must_use]
+    pub const fn with_options(input: &'de [u8], options: DecoderOptions) -> Self {
         Self {
-            input: input.as_bytes(),
+            input: Input::Slice(input),
             offset: 0,
+            discarded: 0,
+            options,
+            config: Config::new(),
+            depth: 0,
+            path: Vec::new(),
         }
     }
 
-    pub fn from_bytes(input: &'de [u8]) -> Self {
-        Deserializer { input, offset: 0 }
+    /// Creates a deserializer that reads from the given reader as it goes,
+    /// rather than needing all of the input up front. Bytes are only read as
+    /// they are needed, so nothing after the value is consumed, but reads are
+    /// small so a reader such as a file or socket should be wrapped in an
+    /// [`io::BufReader`].
+    ///
+    /// Byte strings are copied out of an internal buffer, which holds the
+    /// bytes of the value being deserialized, so types that borrow from the
+    /// input such as `&str` can't be deserialized this way. When values are
+    /// read one after another with a [`StreamDeserializer`], the bytes of each
+    /// are dropped from the buffer once it has been read.
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: io::Read + 'de,
+    {
+        Self::from_reader_with_options(reader, DecoderOptions::default())
+    }
+
+    /// Creates a deserializer that reads from the given reader as it goes,
+    /// decoding according to the given options
+    pub fn from_reader_with_options<R>(reader: R, options: DecoderOptions) -> Self
+    where
+        R: io::Read + 'de,
+    {
+        Self {
+            input: Input::Reader {
+                reader: Box::new(reader),
+                buffer: Vec::new(),
+            },
+            offset: 0,
+            discarded: 0,
+            options,
+            config: Config::new(),
+            depth: 0,
+            path: Vec::new(),
+        }
+    }
+
+    /// Sets how types that bencode has no equivalent for are decoded, which
+    /// should match the config they were encoded with
+    ///
+    /// This can be combined with any of the other ways of making a
+    /// deserializer, such as with [`DecoderOptions`] or from a reader.
+    /// ```
+    /// use bencoding::de::Deserializer;
+    /// use bencoding::{BoolEncoding, Config, DecoderOptions};
+    /// use serde::Deserialize;
+    ///
+    /// let config = Config {
+    ///     bool_encoding: BoolEncoding::Int,
+    ///     ..Config::new()
+    /// };
+    /// let mut deserializer =
+    ///     Deserializer::with_options(b"li1ei0ee", DecoderOptions::strict()).with_config(config);
+    ///
+    /// let flags = Vec::<bool>::deserialize(&mut deserializer).unwrap();
+    /// deserializer.end().unwrap();
+    /// assert_eq!(vec![true, false], flags);
+    /// ```
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Checks that the whole input has been deserialized, which for a reader
+    /// means reading until it ends
+    ///
+    /// # Errors
+    /// Fails if there is any input remaining
+    pub fn end(&mut self) -> Result<()> {
+        if self.input.is_end(self.offset)? {
+            Ok(())
+        } else {
+            Err(BencodingDeserializeError::TrailingCharacters)
+        }
+    }
+
+    /// Returns the index of the next byte to be read from the input
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.discarded + self.offset
+    }
+
+    /// Returns the index just past the end of the value that was
+    /// deserialized, which is the number of bytes it took up when it was the
+    /// first value in the input. This is the same as [`offset`](Self::offset)
+    /// between values.
+    #[must_use]
+    pub const fn end_offset(&self) -> usize {
+        self.offset()
+    }
+
+    /// Drops the bytes that have been read from the start of a reader's
+    /// buffer, which must only be done between values
+    fn discard_read(&mut self) {
+        if self.input.discard(self.offset) {
+            self.discarded += self.offset;
+            self.offset = 0;
+        }
+    }
+
+    /// Attaches the current byte offset, the path to the value being read,
+    /// and the bytes around the offset to an error
+    #[must_use]
+    pub fn locate(&self, error: BencodingDeserializeError) -> BencodingDeserializeError {
+        if let BencodingDeserializeError::Located { .. } = error {
+            return error;
+        }
+
+        let input = self.input.bytes();
+
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Key(key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(&String::from_utf8_lossy(key));
+                }
+                PathSegment::Index(index) => {
+                    // Writing to a `String` can't fail
+                    let _ = write!(path, "[{index}]");
+                }
+            }
+        }
+
+        let offset = self.offset.min(input.len());
+        let start = offset.saturating_sub(SNIPPET_RADIUS);
+        let end = offset.saturating_add(SNIPPET_RADIUS).min(input.len());
+
+        BencodingDeserializeError::Located {
+            error: Box::new(error),
+            offset: self.discarded + offset,
+            path,
+            snippet: input[start..end].escape_ascii().to_string(),
+        }
     }
 }
 
+// How many bytes either side of an error are included in its snippet
+const SNIPPET_RADIUS: usize = 16;
+
 /// Attempts to read a value from a given string
 ///
 /// # Errors
@@ -42,13 +270,7 @@ pub fn from_str<'de, T>(s: &'de str) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    let mut deserializer = Deserializer::from_str(s);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.offset == deserializer.input.len() {
-        Ok(t)
-    } else {
-        Err(BencodingDeserializeError::TrailingCharacters)
-    }
+    from_bytes(s.as_bytes())
 }
 
 /// A convenience function for building a deserializer
@@ -57,49 +279,140 @@ where
 /// # Errors
 /// Fails if deserialization fails
 pub fn from_bytes<'de, T>(s: &'de [u8]) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    from_bytes_with_options(s, DecoderOptions::default())
+}
+
+/// Deserializes a value of type `T` from the start of the bytes, returning it
+/// along with the number of bytes it took up
+///
+/// Unlike [`from_bytes`], trailing bytes aren't an error, so values can be
+/// read one after another, framed from a buffer, or followed by a raw
+/// payload.
+///
+/// ```
+/// let (value, len) = bencoding::from_bytes_prefix::<u32>(b"i42e4:spam").unwrap();
+///
+/// assert_eq!(42, value);
+/// assert_eq!(4, len);
+/// ```
+///
+/// # Errors
+/// Fails if deserialization fails
+pub fn from_bytes_prefix<'de, T>(s: &'de [u8]) -> Result<(T, usize)>
 where
     T: de::Deserialize<'de>,
 {
     let mut deserializer = Deserializer::from_bytes(s);
-    let t = T::deserialize(&mut deserializer)?;
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
 
-    if deserializer.offset == deserializer.input.len() {
-        Ok(t)
-    } else {
-        Err(BencodingDeserializeError::TrailingCharacters)
-    }
+    Ok((t, deserializer.offset()))
+}
+
+/// Deserializes a value of type `T` from the start of the bytes, returning it
+/// along with the bytes after it
+///
+/// Unlike [`from_bytes`], trailing bytes aren't an error, which suits messages
+/// where a bencoded value is followed by a raw payload.
+///
+/// # Errors
+/// Fails if deserialization fails
+pub fn from_bytes_with_remainder<'de, T>(s: &'de [u8]) -> Result<(T, &'de [u8])>
+where
+    T: de::Deserialize<'de>,
+{
+    let (t, len) = from_bytes_prefix(s)?;
+
+    Ok((t, &s[len..]))
 }
 
-impl<'de: 'a, 'a> Deserializer<'de> {
+/// Deserializes a value of type `T` from bytes, decoding according to the
+/// given options
+///
+/// # Errors
+/// Fails if deserialization fails, or if the input breaks a rule enforced by
+/// the options
+pub fn from_bytes_with_options<'de, T>(s: &'de [u8], options: DecoderOptions) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::with_options(s, options);
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end().map_err(|e| deserializer.locate(e))?;
+
+    Ok(t)
+}
+
+/// Deserializes a value of type `T` from bytes, decoding types that bencode
+/// has no equivalent for according to the given config
+///
+/// # Errors
+/// Fails if deserialization fails
+pub fn from_bytes_with_config<'de, T>(s: &'de [u8], config: Config) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::from_bytes(s).with_config(config);
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end().map_err(|e| deserializer.locate(e))?;
+
+    Ok(t)
+}
+
+/// Deserializes a value of type `T` from a reader, such as a file or socket,
+/// reading only as much as is needed at a time. The reader must end after the
+/// value.
+///
+/// # Errors
+/// Fails if reading fails, if deserialization fails, or if there is more
+/// input after the value
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::from_reader(reader);
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end().map_err(|e| deserializer.locate(e))?;
+
+    Ok(t)
+}
+
+impl Deserializer<'_> {
     /// Peeks at the next byte in the input without consuming it
-    fn peek_byte(&self) -> Result<&u8> {
-        self.input
-            .get(self.offset)
-            .ok_or(BencodingDeserializeError::OutOfCharacters)
+    fn peek_byte(&mut self) -> Result<u8> {
+        if self.input.fill_to(self.offset + 1)? {
+            Ok(self.input.bytes()[self.offset])
+        } else {
+            Err(BencodingDeserializeError::OutOfCharacters)
+        }
     }
 
     /// Returns and consumes the byte at the current offset
-    fn read_byte(&'a mut self) -> Result<u8> {
-        let c = *self.peek_byte()?;
+    fn read_byte(&mut self) -> Result<u8> {
+        let c = self.peek_byte()?;
         self.offset += 1;
         Ok(c)
     }
 
-    /// Returns and consumes the first n bytes from the current offset
-    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
-        let bytes = &self
-            .input
-            .get(self.offset..self.offset + len)
+    /// Consumes the first n bytes from the current offset, returning their
+    /// position in the input
+    fn read_bytes(&mut self, len: usize) -> Result<Range<usize>> {
+        let end = self
+            .offset
+            .checked_add(len)
             .ok_or(BencodingDeserializeError::OutOfCharacters)?;
 
-        self.offset += len;
+        if !self.input.fill_to(end)? {
+            return Err(BencodingDeserializeError::OutOfCharacters);
+        }
 
-        Ok(bytes)
-    }
+        let range = self.offset..end;
+        self.offset = end;
 
-    fn read_string(&mut self, len: usize) -> Result<&'a str> {
-        std::str::from_utf8(self.read_bytes(len)?)
-            .map_err(|_| BencodingDeserializeError::InputNotUtf8)
+        Ok(range)
     }
 
     /// Read bytes from the input until it reaches a non-numeric ascii byte, then
@@ -109,12 +422,31 @@ impl<'de: 'a, 'a> Deserializer<'de> {
         T: PrimInt + Display,
     {
         // Find the first non ascii-numeric byte
-        let end_index = self
-            .position_next(|&c| !('0'..'9').contains(&(c as char)))
-            .ok_or(BencodingDeserializeError::NoFoundClosingDeliminator(':'))?;
+        let start_index = self.offset;
+        let mut end_index = start_index;
+        let mut more = self.input.fill_to(end_index + 1)?;
+        while more && self.input.bytes()[end_index].is_ascii_digit() {
+            end_index += 1;
+            more = self.input.fill_to(end_index + 1)?;
+        }
+
+        let digits = &self.input.bytes()[start_index..end_index];
+
+        // With no digits before the end of the input, they may just not have
+        // arrived yet
+        if digits.is_empty() && !more {
+            return Err(BencodingDeserializeError::OutOfCharacters);
+        }
+        if digits.is_empty() {
+            return Err(BencodingDeserializeError::EmptyInteger);
+        }
+
+        if self.options.strict && digits.len() > 1 && digits[0] == b'0' {
+            return Err(BencodingDeserializeError::LeadingZero);
+        }
 
-        let ascii_string = std::str::from_utf8(&self.input[self.offset..end_index])
-            .expect("Trying to read integer that is not valid ascii");
+        let ascii_string =
+            std::str::from_utf8(digits).map_err(|_| BencodingDeserializeError::ParseIntError)?;
 
         let result = <T as Num>::from_str_radix(ascii_string, 10)
             .map_err(|_| BencodingDeserializeError::ParseIntError);
@@ -132,6 +464,11 @@ impl<'de: 'a, 'a> Deserializer<'de> {
             return Err(BencodingDeserializeError::NoFoundOpeningDeliminator('i'));
         }
 
+        // A negative number can never fit in an unsigned type
+        if self.peek_byte()? == b'-' {
+            return Err(BencodingDeserializeError::ParseIntError);
+        }
+
         let result = self.read_integer::<T>();
 
         if self.read_byte()? == b'e' {
@@ -151,9 +488,15 @@ impl<'de: 'a, 'a> Deserializer<'de> {
             return Err(BencodingDeserializeError::NoFoundOpeningDeliminator('i'));
         }
 
-        let result = if *self.peek_byte()? == b'-' {
+        let result = if self.peek_byte()? == b'-' {
             self.read_byte()?;
-            -self.read_integer::<T>()?
+            let result = self.read_integer::<T>()?;
+
+            if self.options.strict && result.is_zero() {
+                return Err(BencodingDeserializeError::NegativeZero);
+            }
+
+            -result
         } else {
             self.read_integer::<T>()?
         };
@@ -165,7 +508,8 @@ impl<'de: 'a, 'a> Deserializer<'de> {
         }
     }
 
-    fn parse_bytes(&mut self) -> Result<&'a [u8]> {
+    /// Parses a byte string, returning the position of its bytes in the input
+    fn parse_bytes(&mut self) -> Result<Range<usize>> {
         let bytes_len = self.read_integer::<usize>()?;
 
         if self.read_byte()? != b':' {
@@ -175,23 +519,27 @@ impl<'de: 'a, 'a> Deserializer<'de> {
         self.read_bytes(bytes_len)
     }
 
-    fn parse_str(&mut self) -> Result<&'a str> {
-        let str_len = self.read_integer::<usize>()?;
-
-        if self.read_byte()? != b':' {
-            return Err(BencodingDeserializeError::NoFoundColon);
+    /// Enters a list or dictionary, failing if that nests them too deeply
+    const fn enter(&mut self) -> Result<()> {
+        if self.depth >= self.options.max_depth {
+            return Err(BencodingDeserializeError::DepthLimitExceeded(
+                self.options.max_depth,
+            ));
         }
-
-        self.read_string(str_len)
+        self.depth += 1;
+        Ok(())
     }
 
-    fn position_next<P>(&self, predicate: P) -> Option<usize>
-    where
-        P: Fn(&u8) -> bool,
-    {
-        let index = self.input[self.offset..].iter().position(predicate)?;
+    /// Consumes the given bytes if they come next in the input
+    fn consume_if_next(&mut self, expected: &[u8]) -> Result<bool> {
+        let end = self.offset + expected.len();
 
-        Some(self.offset + index)
+        if self.input.fill_to(end)? && &self.input.bytes()[self.offset..end] == expected {
+            self.offset = end;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
     }
 }
 
@@ -205,9 +553,12 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        match *self.peek_byte()? as char {
+        match self.peek_byte()? as char {
+            // Byte strings are visited as strings where they are valid UTF-8,
+            // so that both string and byte based visitors can accept them
             '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                self.deserialize_str(visitor)
+                let range = self.parse_bytes()?;
+                self.input.visit_byte_string(range, visitor)
             }
             'i' => self.deserialize_i64(visitor),
             'l' => self.deserialize_seq(visitor),
@@ -216,11 +567,24 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         }
     }
 
-    fn deserialize_bool<V>(self, _: V) -> Result<V::Value>
+    // Bencode has no booleans, but the config can allow them to be read from
+    // the integers 0 and 1
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(BencodingDeserializeError::InvalidTypeBool)
+        if self.config.bool_encoding == BoolEncoding::Error {
+            return Err(BencodingDeserializeError::InvalidTypeBool);
+        }
+
+        match self.parse_unsigned::<u64>()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            n => Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(n),
+                &"0 or 1",
+            )),
+        }
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
@@ -279,30 +643,39 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_u64(self.parse_unsigned()?)
     }
 
-    // Float parsing is stupidly hard.
+    // Bencode only has integers, so there are no floats to read.
     fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(BencodingDeserializeError::InvalidTypeFloat)
     }
 
-    // Float parsing is stupidly hard.
     fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(BencodingDeserializeError::InvalidTypeFloat)
     }
 
-    // The `Serializer` implementation on the previous page serialized chars as
-    // single-character strings so handle that representation here.
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
+    // The `Serializer` serializes chars as single-character strings so handle
+    // that representation here.
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        // Parse a string, check that it is one character, call `visit_char`.
-        unimplemented!()
+        let range = self.parse_bytes()?;
+        let s = std::str::from_utf8(&self.input.bytes()[range])
+            .map_err(|_| BencodingDeserializeError::InputNotUtf8)?;
+
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(s),
+                &"a single character",
+            )),
+        }
     }
 
     // Refer to the "Understanding deserializer lifetimes" page for information
@@ -311,7 +684,8 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.parse_str()?)
+        let range = self.parse_bytes()?;
+        self.input.visit_str(range, visitor)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -325,45 +699,40 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_bytes(self.parse_bytes()?)
+        let range = self.parse_bytes()?;
+        self.input.visit_bytes(range, visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_bytes(self.parse_bytes()?)
+        let range = self.parse_bytes()?;
+        self.input.visit_bytes(range, visitor)
     }
 
-    // An absent optional is represented as the JSON `null` and a present
-    // optional is represented as just the contained value.
-    //
-    // As commented in `Serializer` implementation, this is a lossy
-    // representation. For example the values `Some(())` and `None` both
-    // serialize as just `null`. Unfortunately this is typically what people
-    // expect when working with JSON. Other formats are encouraged to behave
-    // more intelligently if possible.
+    // Bencode has no null, so an absent optional is never written, and a
+    // present optional is represented as just the contained value. A struct
+    // field that is missing is filled in as `None` by the struct's
+    // `Deserialize` implementation.
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.input.starts_with(b"null") {
-            self.input = &self.input["null".len()..];
-            //self.input = self.input.split_off("null".len());
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
-        }
+        visitor.visit_some(self)
     }
 
-    // In Serde, unit means an anonymous value containing no data.
+    // In Serde, unit means an anonymous value containing no data. The config
+    // can allow it to be read from an empty byte string.
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.input.starts_with(b"null") {
-            self.input = &self.input["null".len()..];
-            //self.input = self.input.split_off("null".len());
+        if self.config.unit_encoding == UnitEncoding::Error {
+            return Err(BencodingDeserializeError::InvalidTypeUnit);
+        }
+
+        if self.consume_if_next(b"0:")? {
             visitor.visit_unit()
         } else {
             Err(BencodingDeserializeError::ExpectedNull)
@@ -380,25 +749,34 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
     // As is done here, serializers are encouraged to treat newtype structs as
     // insignificant wrappers around the data they contain. That means not
-    // parsing anything other than the contained value.
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    // parsing anything other than the contained value. The exception is
+    // `RawBencode`, which is given the raw bytes of the value.
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if name == RAW_TOKEN {
+            let start = self.offset;
+            de::IgnoredAny::deserialize(&mut *self)?;
+            return self.input.visit_bytes(start..self.offset, visitor);
+        }
+
         visitor.visit_newtype_struct(self)
     }
 
     // Deserialization of compound types like sequences and maps happens by
     // passing the visitor an "Access" object that gives it the ability to
     // iterate through the data contained in the sequence.
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         // Parse the opening character of the sequence.
         if self.read_byte()? == b'l' {
+            self.enter()?;
             // Give the visitor access to each element of the sequence.
-            let value = visitor.visit_seq(&mut self)?;
+            let value = visitor.visit_seq(ListAccess { de: self, index: 0 })?;
+            self.depth -= 1;
             // Parse the closing character of the sequence.
             if self.read_byte()? == b'e' {
                 Ok(value)
@@ -439,14 +817,16 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // Much like `deserialize_seq` but calls the visitors `visit_map` method
     // with a `MapAccess` implementation, rather than the visitor's `visit_seq`
     // method with a `SeqAccess` implementation.
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         // Parse the opening brace of the map.
         if self.read_byte()? == b'd' {
+            self.enter()?;
             // Give the visitor access to each entry of the map.
-            let value = visitor.visit_map(&mut self)?;
+            let value = visitor.visit_map(DictAccess::new(self))?;
+            self.depth -= 1;
             // Parse the closing brace of the map.
             if self.read_byte()? == b'e' {
                 Ok(value)
@@ -470,27 +850,46 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_map(visitor)
     }
 
+    // Enums are externally tagged. Unit variants are just the variant's name,
+    // and variants with data are a dictionary with a single entry mapping the
+    // name to the data, as `d<name><data>e`.
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
         _variants: &'static [&'static str],
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        panic!("This version of bencoding doesn't support enums")
+        if self.peek_byte()? == b'd' {
+            self.read_byte()?;
+            self.enter()?;
+            let value = visitor.visit_enum(VariantAccess::new(self))?;
+            self.depth -= 1;
+            // Parse the closing brace of the dictionary.
+            if self.read_byte()? == b'e' {
+                Ok(value)
+            } else {
+                Err(BencodingDeserializeError::NoFoundClosingDeliminator('e'))
+            }
+        } else {
+            visitor.visit_enum(UnitVariantAccess { de: self })
+        }
     }
 
     // An identifier in Serde is the type that identifies a field of a struct or
     // the variant of an enum. In JSON, struct fields and enum variants are
     // represented as strings. In other formats they may be represented as
-    // numeric indices.
+    // numeric indices. In bencode they are byte strings, which are visited as
+    // bytes when they aren't valid UTF-8, so that unknown keys such as raw
+    // hashes can be skipped over.
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        let range = self.parse_bytes()?;
+        self.input.visit_byte_string(range, visitor)
     }
 
     // Like `deserialize_any` but indicates to the `Deserializer` that it makes
@@ -512,7 +911,14 @@ impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 }
 
-impl<'de> SeqAccess<'de> for Deserializer<'de> {
+// Gives a visitor access to each element of a list, keeping track of the
+// position of the current element for error messages.
+struct ListAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'_, 'de> {
     type Error = BencodingDeserializeError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
@@ -520,15 +926,74 @@ impl<'de> SeqAccess<'de> for Deserializer<'de> {
         T: de::DeserializeSeed<'de>,
     {
         // Check if there are no more elements.
-        if self.peek_byte()? == &b'e' {
+        if self.de.peek_byte()? == b'e' {
             return Ok(None);
         }
         // Deserialize an array element.
-        seed.deserialize(self).map(Some)
+        self.de.path.push(PathSegment::Index(self.index));
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.path.pop();
+
+        self.index += 1;
+        Ok(Some(value))
     }
 }
 
-impl<'de> MapAccess<'de> for Deserializer<'de> {
+// Gives a visitor access to each entry of a dictionary, keeping track of the
+// current key for error messages, and the previous key so that key order can
+// be checked when decoding strictly.
+struct DictAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    // The positions of the keys' bytes in the input
+    key: Option<Range<usize>>,
+    previous_key: Option<Range<usize>>,
+}
+
+impl<'a, 'de> DictAccess<'a, 'de> {
+    const fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Self {
+            de,
+            key: None,
+            previous_key: None,
+        }
+    }
+
+    // Reads the next key without consuming it, returning the position of its
+    // bytes in the input
+    fn peek_key(&mut self) -> Result<Range<usize>> {
+        let offset = self.de.offset;
+        let key = self.de.parse_bytes()?;
+        self.de.offset = offset;
+        Ok(key)
+    }
+
+    // Checks that the current key sorts strictly after the previous key
+    fn check_key_order(&mut self, key: Range<usize>) -> Result<()> {
+        if let Some(previous_key) = self.previous_key.take() {
+            let input = self.de.input.bytes();
+            let (previous_key, key) = (&input[previous_key], &input[key.clone()]);
+
+            match previous_key.cmp(key) {
+                Ordering::Less => {}
+                Ordering::Equal => {
+                    return Err(BencodingDeserializeError::DuplicateKey(
+                        String::from_utf8_lossy(key).into_owned(),
+                    ))
+                }
+                Ordering::Greater => {
+                    return Err(BencodingDeserializeError::UnsortedKey(
+                        String::from_utf8_lossy(key).into_owned(),
+                    ))
+                }
+            }
+        }
+
+        self.previous_key = Some(key);
+        Ok(())
+    }
+}
+
+impl<'de> MapAccess<'de> for DictAccess<'_, 'de> {
     type Error = BencodingDeserializeError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
@@ -536,11 +1001,20 @@ impl<'de> MapAccess<'de> for Deserializer<'de> {
         K: de::DeserializeSeed<'de>,
     {
         // Check if there are no more entries.
-        if self.peek_byte()? == &b'e' {
+        if self.de.peek_byte()? == b'e' {
             return Ok(None);
         }
+
+        let key = self.peek_key()?;
+        self.key = Some(key.clone());
+
+        if self.de.options.strict {
+            self.check_key_order(key)?;
+        }
+
         // Deserialize a map key.
-        seed.deserialize(&mut *self).map(Some)
+        seed.deserialize(MapKeyDeserializer { de: &mut *self.de })
+            .map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
@@ -548,6 +1022,144 @@ impl<'de> MapAccess<'de> for Deserializer<'de> {
         V: de::DeserializeSeed<'de>,
     {
         // Deserialize a map value.
-        seed.deserialize(self)
+        let key = self.key.take().unwrap_or_default();
+        let key = self.de.input.bytes()[key].to_vec();
+        self.de.path.push(PathSegment::Key(key));
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.path.pop();
+
+        Ok(value)
+    }
+}
+
+// Gives a visitor access to an enum variant with data, the name of which is
+// the only key of a dictionary.
+struct VariantAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    // The position of the variant's name in the input
+    name: Range<usize>,
+}
+
+impl<'a, 'de> VariantAccess<'a, 'de> {
+    const fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Self { de, name: 0..0 }
+    }
+
+    // Deserializes the variant's data, recording the variant's name in the
+    // path for error messages
+    fn deserialize_data<F, T>(self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<'de>) -> Result<T>,
+    {
+        let name = self.de.input.bytes()[self.name].to_vec();
+        self.de.path.push(PathSegment::Key(name));
+        let value = f(&mut *self.de)?;
+        self.de.path.pop();
+
+        Ok(value)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = BencodingDeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let offset = self.de.offset;
+        self.name = self.de.parse_bytes()?;
+        self.de.offset = offset;
+
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = BencodingDeserializeError;
+
+    // A unit variant has no data, but whatever it's mapped to is skipped over
+    fn unit_variant(self) -> Result<()> {
+        self.deserialize_data(|de| de::IgnoredAny::deserialize(de).map(|_| ()))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        self.deserialize_data(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_data(|de| de::Deserializer::deserialize_seq(de, visitor))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_data(|de| de::Deserializer::deserialize_map(de, visitor))
+    }
+}
+
+// Gives a visitor access to a unit enum variant, which is just the variant's
+// name.
+struct UnitVariantAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for UnitVariantAccess<'_, 'de> {
+    type Error = BencodingDeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for UnitVariantAccess<'_, 'de> {
+    type Error = BencodingDeserializeError;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, _seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::UnitVariant,
+            &"struct variant",
+        ))
     }
 }
@@ -0,0 +1,106 @@
+use std::io::Read;
+use std::ops::Range;
+
+use serde::de::Visitor;
+
+use super::{BencodingDeserializeError, Result};
+
+// The source of the bytes being deserialized. A slice is available all at
+// once, so byte strings can be borrowed from it. A reader is pulled from only
+// as more bytes are needed, into a buffer that byte strings are copied out of.
+pub(super) enum Input<'de> {
+    Slice(&'de [u8]),
+    Reader {
+        reader: Box<dyn Read + 'de>,
+        buffer: Vec<u8>,
+    },
+}
+
+impl<'de> Input<'de> {
+    /// Returns every byte of the input that is available so far
+    pub(super) fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Slice(slice) => slice,
+            Self::Reader { buffer, .. } => buffer,
+        }
+    }
+
+    /// Makes sure at least `len` bytes of the input are available, reading
+    /// exactly as many more as are needed from a reader. Returns `false` if
+    /// the input ends first.
+    pub(super) fn fill_to(&mut self, len: usize) -> Result<bool> {
+        match self {
+            Self::Slice(slice) => Ok(slice.len() >= len),
+            Self::Reader { reader, buffer } => {
+                if buffer.len() < len {
+                    let missing = (len - buffer.len()) as u64;
+                    reader
+                        .by_ref()
+                        .take(missing)
+                        .read_to_end(buffer)
+                        .map_err(BencodingDeserializeError::Io)?;
+                }
+
+                Ok(buffer.len() >= len)
+            }
+        }
+    }
+
+    /// Drops the first `len` bytes of a reader's buffer, returning whether
+    /// they were dropped, which they can't be from a slice
+    pub(super) fn discard(&mut self, len: usize) -> bool {
+        match self {
+            Self::Slice(_) => false,
+            Self::Reader { buffer, .. } => {
+                buffer.drain(..len);
+                true
+            }
+        }
+    }
+
+    /// Returns whether there are no more bytes after `offset`, reading the
+    /// next byte from a reader to find out, which is kept to be read later
+    pub(super) fn is_end(&mut self, offset: usize) -> Result<bool> {
+        Ok(!self.fill_to(offset + 1)?)
+    }
+
+    /// Visits the given bytes, borrowing them from the input when it's a slice
+    pub(super) fn visit_bytes<V>(&self, range: Range<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Slice(slice) => visitor.visit_borrowed_bytes(&slice[range]),
+            Self::Reader { buffer, .. } => visitor.visit_bytes(&buffer[range]),
+        }
+    }
+
+    /// Visits the given bytes as a string, failing if they aren't valid UTF-8
+    pub(super) fn visit_str<V>(&self, range: Range<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Slice(slice) => visitor.visit_borrowed_str(to_str(&slice[range])?),
+            Self::Reader { buffer, .. } => visitor.visit_str(to_str(&buffer[range])?),
+        }
+    }
+
+    /// Visits the given bytes as a string where they are valid UTF-8, so that
+    /// both string and byte based visitors can accept them, and as bytes
+    /// otherwise
+    pub(super) fn visit_byte_string<V>(&self, range: Range<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if to_str(&self.bytes()[range.clone()]).is_ok() {
+            self.visit_str(range, visitor)
+        } else {
+            self.visit_bytes(range, visitor)
+        }
+    }
+}
+
+fn to_str(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes).map_err(|_| BencodingDeserializeError::InputNotUtf8)
+}
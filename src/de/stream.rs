@@ -0,0 +1,85 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use super::{Deserializer, Result};
+
+/// An iterator over consecutive bencoded values in the input, such as
+/// messages framed back to back or a log file that values are appended to
+///
+/// Iteration stops at the end of the input, or after the first error, as
+/// there's no telling where the next value would start.
+///
+/// ```
+/// use bencoding::de::Deserializer;
+/// use bencoding::StreamDeserializer;
+///
+/// let mut stream = StreamDeserializer::<u32>::new(Deserializer::from_bytes(b"i1ei2ei3e"));
+///
+/// assert_eq!(Some(1), stream.next().transpose().unwrap());
+/// assert_eq!(3, stream.end_offset());
+/// assert_eq!(vec![2, 3], stream.collect::<Result<Vec<_>, _>>().unwrap());
+/// ```
+pub struct StreamDeserializer<'de, T> {
+    deserializer: Deserializer<'de>,
+    // The index just past the last value read successfully
+    end_offset: usize,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    #[must_use]
+    pub const fn new(deserializer: Deserializer<'de>) -> Self {
+        Self {
+            end_offset: deserializer.offset(),
+            deserializer,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// Returns the index just past the end of the last value read
+    /// successfully, which is the number of bytes the values so far took up
+    #[must_use]
+    pub const fn end_offset(&self) -> usize {
+        self.end_offset
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        // Nothing before the next value is needed any more
+        self.deserializer.discard_read();
+
+        let offset = self.deserializer.offset;
+        let result = match self.deserializer.input.is_end(offset) {
+            Ok(true) => return None,
+            Ok(false) => T::deserialize(&mut self.deserializer),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(t) => {
+                self.end_offset = self.deserializer.offset();
+                Some(Ok(t))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(self.deserializer.locate(e)))
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+use super::{MAX_SALT_LEN, MAX_VALUE_LEN};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    #[error("an item's value is {0} bytes, but may be at most {MAX_VALUE_LEN}")]
+    ValueTooLong(usize),
+    #[error("an item's salt is {0} bytes, but may be at most {MAX_SALT_LEN}")]
+    SaltTooLong(usize),
+}
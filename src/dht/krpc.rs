@@ -0,0 +1,530 @@
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+use super::storage::check_lengths;
+use super::{NodeId, NodeInfo, PUBLIC_KEY_LEN, SIGNATURE_LEN};
+use crate::tracker::{compact, INFO_HASH_LEN};
+use crate::RawBencode;
+
+/// A KRPC message, the envelope of every DHT query, response and error
+///
+/// ```
+/// use bencoding::dht::{KrpcBody, KrpcMessage, Query};
+///
+/// let message: KrpcMessage =
+///     bencoding::from_bytes(b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe").unwrap();
+///
+/// assert_eq!(b"aa", &message.transaction_id[..]);
+/// assert_eq!(
+///     KrpcBody::Query(Query::Ping { id: *b"abcdefghij0123456789" }),
+///     message.body
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MessageFields", into = "MessageFields")]
+pub struct KrpcMessage {
+    /// Chosen by the querying node, and echoed back in the response
+    pub transaction_id: Vec<u8>,
+    /// The client's name and version, from `v`
+    pub version: Option<Vec<u8>>,
+    pub body: KrpcBody,
+}
+
+impl KrpcMessage {
+    #[must_use]
+    pub const fn new(transaction_id: Vec<u8>, body: KrpcBody) -> Self {
+        Self {
+            transaction_id,
+            version: None,
+            body,
+        }
+    }
+}
+
+/// Whether a [`KrpcMessage`] is a query, a response or an error, from `y`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KrpcBody {
+    Query(Query),
+    Response(Response),
+    Error(KrpcError),
+}
+
+/// A query, with its method from `q` and its arguments from `a`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Ping {
+        id: NodeId,
+    },
+    FindNode {
+        id: NodeId,
+        target: NodeId,
+    },
+    GetPeers {
+        id: NodeId,
+        info_hash: [u8; INFO_HASH_LEN],
+    },
+    AnnouncePeer {
+        id: NodeId,
+        info_hash: [u8; INFO_HASH_LEN],
+        /// The port the peer is listening on, which is ignored if
+        /// `implied_port` is set
+        port: u16,
+        /// The token from an earlier `get_peers` response
+        token: Vec<u8>,
+        /// Whether to use the port the query came from instead of `port`
+        implied_port: bool,
+    },
+    /// Gets a stored item, as described by BEP 44
+    Get {
+        id: NodeId,
+        /// The item's target, from [`immutable_target`](super::immutable_target)
+        /// or [`mutable_target`](super::mutable_target)
+        target: NodeId,
+        /// Only return a mutable item if it's newer than this
+        seq: Option<i64>,
+    },
+    /// Stores an item, as described by BEP 44. A mutable item has a public
+    /// key, signature and sequence number, and an immutable item has none.
+    Put {
+        id: NodeId,
+        /// The token from an earlier `get` response
+        token: Vec<u8>,
+        /// The item's value, which is any bencoded value
+        v: RawBencode,
+        k: Option<[u8; PUBLIC_KEY_LEN]>,
+        sig: Option<[u8; SIGNATURE_LEN]>,
+        seq: Option<i64>,
+        /// Only store the item if the stored item's sequence number is this
+        cas: Option<i64>,
+        salt: Option<Vec<u8>>,
+    },
+}
+
+impl Query {
+    /// Returns the method name sent in `q`
+    #[must_use]
+    pub const fn method(&self) -> &'static str {
+        match self {
+            Self::Ping { .. } => "ping",
+            Self::FindNode { .. } => "find_node",
+            Self::GetPeers { .. } => "get_peers",
+            Self::AnnouncePeer { .. } => "announce_peer",
+            Self::Get { .. } => "get",
+            Self::Put { .. } => "put",
+        }
+    }
+
+    /// Returns the id of the querying node
+    #[must_use]
+    pub const fn id(&self) -> &NodeId {
+        match self {
+            Self::Ping { id }
+            | Self::FindNode { id, .. }
+            | Self::GetPeers { id, .. }
+            | Self::AnnouncePeer { id, .. }
+            | Self::Get { id, .. }
+            | Self::Put { id, .. } => id,
+        }
+    }
+}
+
+/// The values returned by a query, from `r`
+///
+/// Responses don't say which query they answer, so every value other than
+/// `id` is optional. A `ping`, `announce_peer` or `put` response only has an
+/// `id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    /// The id of the responding node
+    #[serde(with = "serde_bytes")]
+    pub id: NodeId,
+    /// The closest IPv4 nodes to the target, from the compact `nodes`
+    #[serde(with = "nodes", default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<NodeInfo>,
+    /// The closest IPv6 nodes to the target, from the compact `nodes6`
+    #[serde(with = "nodes6", default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes6: Vec<NodeInfo>,
+    /// A token for a later `announce_peer`, sent in answer to `get_peers`
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<Vec<u8>>,
+    /// Peers for the torrent in `get_peers`, from a list of compact addresses
+    #[serde(with = "values", default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<SocketAddr>,
+    /// The stored item's value, sent in answer to `get`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v: Option<RawBencode>,
+    /// The mutable item's public key
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<[u8; PUBLIC_KEY_LEN]>,
+    /// The mutable item's signature
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    pub sig: Option<[u8; SIGNATURE_LEN]>,
+    /// The mutable item's sequence number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<i64>,
+}
+
+impl Response {
+    /// Creates a response with only the responding node's id
+    #[must_use]
+    pub const fn new(id: NodeId) -> Self {
+        Self {
+            id,
+            nodes: Vec::new(),
+            nodes6: Vec::new(),
+            token: None,
+            values: Vec::new(),
+            v: None,
+            k: None,
+            sig: None,
+            seq: None,
+        }
+    }
+}
+
+/// An error, sent as a list of its code and message in `e`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KrpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl KrpcError {
+    pub const GENERIC: i64 = 201;
+    pub const SERVER: i64 = 202;
+    pub const PROTOCOL: i64 = 203;
+    pub const METHOD_UNKNOWN: i64 = 204;
+
+    pub fn new<S>(code: i64, message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Serialize for KrpcError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.code)?;
+        tuple.serialize_element(&self.message)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for KrpcError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (code, message) = <(i64, String)>::deserialize(deserializer)?;
+        Ok(Self { code, message })
+    }
+}
+
+// The fields of a message as they are encoded, where the arguments of every
+// query are merged
+#[derive(Serialize, Deserialize)]
+struct MessageFields {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<QueryArgs>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    e: Option<KrpcError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r: Option<Response>,
+    t: ByteBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v: Option<ByteBuf>,
+    y: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct QueryArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cas: Option<i64>,
+    #[serde(with = "serde_bytes")]
+    id: NodeId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    implied_port: Option<u8>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    info_hash: Option<[u8; INFO_HASH_LEN]>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    k: Option<[u8; PUBLIC_KEY_LEN]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    salt: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seq: Option<i64>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    sig: Option<[u8; SIGNATURE_LEN]>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    target: Option<NodeId>,
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    token: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v: Option<RawBencode>,
+}
+
+impl TryFrom<MessageFields> for KrpcMessage {
+    type Error = String;
+
+    fn try_from(fields: MessageFields) -> Result<Self, Self::Error> {
+        let body = match &fields.y[..] {
+            "q" => {
+                let method = fields.q.ok_or("a query must have a method in 'q'")?;
+                let args = fields.a.ok_or("a query must have arguments in 'a'")?;
+                KrpcBody::Query(parse_query(&method, args)?)
+            }
+            "r" => KrpcBody::Response(fields.r.ok_or("a response must have 'r'")?),
+            "e" => KrpcBody::Error(fields.e.ok_or("an error must have 'e'")?),
+            y => return Err(format!("unknown message type '{y}'")),
+        };
+
+        Ok(Self {
+            transaction_id: fields.t.into_vec(),
+            version: fields.v.map(ByteBuf::into_vec),
+            body,
+        })
+    }
+}
+
+fn parse_query(method: &str, args: QueryArgs) -> Result<Query, String> {
+    let missing = |arg| format!("a '{method}' query must have '{arg}'");
+    let id = args.id;
+
+    Ok(match method {
+        "ping" => Query::Ping { id },
+        "find_node" => Query::FindNode {
+            id,
+            target: args.target.ok_or_else(|| missing("target"))?,
+        },
+        "get_peers" => Query::GetPeers {
+            id,
+            info_hash: args.info_hash.ok_or_else(|| missing("info_hash"))?,
+        },
+        "announce_peer" => Query::AnnouncePeer {
+            id,
+            info_hash: args.info_hash.ok_or_else(|| missing("info_hash"))?,
+            port: args.port.ok_or_else(|| missing("port"))?,
+            token: args.token.ok_or_else(|| missing("token"))?,
+            implied_port: args.implied_port.unwrap_or_default() != 0,
+        },
+        "get" => Query::Get {
+            id,
+            target: args.target.ok_or_else(|| missing("target"))?,
+            seq: args.seq,
+        },
+        "put" => {
+            let v = args.v.ok_or_else(|| missing("v"))?;
+            check_lengths(args.salt.as_deref().unwrap_or_default(), &v)
+                .map_err(|e| e.to_string())?;
+
+            Query::Put {
+                id,
+                token: args.token.ok_or_else(|| missing("token"))?,
+                v,
+                k: args.k,
+                sig: args.sig,
+                seq: args.seq,
+                cas: args.cas,
+                salt: args.salt,
+            }
+        }
+        _ => return Err(format!("unknown query method '{method}'")),
+    })
+}
+
+impl From<KrpcMessage> for MessageFields {
+    fn from(message: KrpcMessage) -> Self {
+        let mut fields = Self {
+            a: None,
+            e: None,
+            q: None,
+            r: None,
+            t: ByteBuf::from(message.transaction_id),
+            v: message.version.map(ByteBuf::from),
+            y: String::new(),
+        };
+
+        match message.body {
+            KrpcBody::Query(query) => {
+                fields.y = "q".to_string();
+                fields.q = Some(query.method().to_string());
+                fields.a = Some(query_args(query));
+            }
+            KrpcBody::Response(response) => {
+                fields.y = "r".to_string();
+                fields.r = Some(response);
+            }
+            KrpcBody::Error(error) => {
+                fields.y = "e".to_string();
+                fields.e = Some(error);
+            }
+        }
+
+        fields
+    }
+}
+
+fn query_args(query: Query) -> QueryArgs {
+    match query {
+        Query::Ping { id } => QueryArgs {
+            id,
+            ..QueryArgs::default()
+        },
+        Query::FindNode { id, target } => QueryArgs {
+            id,
+            target: Some(target),
+            ..QueryArgs::default()
+        },
+        Query::GetPeers { id, info_hash } => QueryArgs {
+            id,
+            info_hash: Some(info_hash),
+            ..QueryArgs::default()
+        },
+        Query::AnnouncePeer {
+            id,
+            info_hash,
+            port,
+            token,
+            implied_port,
+        } => QueryArgs {
+            id,
+            implied_port: if implied_port { Some(1) } else { None },
+            info_hash: Some(info_hash),
+            port: Some(port),
+            token: Some(token),
+            ..QueryArgs::default()
+        },
+        Query::Get { id, target, seq } => QueryArgs {
+            id,
+            target: Some(target),
+            seq,
+            ..QueryArgs::default()
+        },
+        Query::Put {
+            id,
+            token,
+            v,
+            k,
+            sig,
+            seq,
+            cas,
+            salt,
+        } => QueryArgs {
+            cas,
+            id,
+            k,
+            salt,
+            seq,
+            sig,
+            token: Some(token),
+            v: Some(v),
+            ..QueryArgs::default()
+        },
+    }
+}
+
+// `nodes` and `nodes6` are compact node info
+mod nodes {
+    use super::{de, Deserialize, Deserializer, NodeInfo, Serializer};
+    use serde_bytes::ByteBuf;
+
+    pub fn serialize<S>(nodes: &[NodeInfo], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&NodeInfo::encode_v4(nodes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<NodeInfo>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        NodeInfo::decode_v4(&bytes).ok_or_else(|| {
+            de::Error::invalid_length(bytes.len(), &"a whole number of compact IPv4 nodes")
+        })
+    }
+}
+
+mod nodes6 {
+    use super::{de, Deserialize, Deserializer, NodeInfo, Serializer};
+    use serde_bytes::ByteBuf;
+
+    pub fn serialize<S>(nodes: &[NodeInfo], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&NodeInfo::encode_v6(nodes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<NodeInfo>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        NodeInfo::decode_v6(&bytes).ok_or_else(|| {
+            de::Error::invalid_length(bytes.len(), &"a whole number of compact IPv6 nodes")
+        })
+    }
+}
+
+// `values` is a list of peers, each of which is a compact address of either
+// kind
+mod values {
+    use std::net::SocketAddr;
+
+    use super::{compact, de, Deserialize, Deserializer, Serializer};
+    use serde_bytes::{ByteBuf, Bytes};
+
+    pub fn serialize<S>(values: &[SocketAddr], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let peers: Vec<Vec<u8>> = values
+            .iter()
+            .map(|peer| {
+                [
+                    compact::encode_v4(Some(peer)),
+                    compact::encode_v6(Some(peer)),
+                ]
+                .concat()
+            })
+            .collect();
+        serializer.collect_seq(peers.iter().map(|peer| Bytes::new(peer)))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<SocketAddr>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<ByteBuf>::deserialize(deserializer)?
+            .iter()
+            .map(|peer| {
+                let addrs = match peer.len() {
+                    compact::V4_LEN => compact::decode_v4(peer),
+                    compact::V6_LEN => compact::decode_v6(peer),
+                    _ => None,
+                };
+                addrs
+                    .and_then(|addrs| addrs.first().copied())
+                    .ok_or_else(|| de::Error::invalid_length(peer.len(), &"a compact peer"))
+            })
+            .collect()
+    }
+}
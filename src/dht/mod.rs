@@ -0,0 +1,107 @@
+//! Messages of the mainline DHT, as described by BEP 5, and BEP 44 for
+//! storing arbitrary items.
+//!
+//! Every message is a bencoded [`KrpcMessage`], which is a query, a response
+//! to one, or an error. Nodes are sent in the compact node info format, which
+//! is a node's id followed by its compact address.
+
+use std::convert::TryInto;
+use std::net::SocketAddr;
+
+use crate::tracker::compact;
+
+mod err;
+mod krpc;
+mod storage;
+
+pub use err::StorageError;
+pub use krpc::{KrpcBody, KrpcError, KrpcMessage, Query, Response};
+pub use storage::{
+    immutable_target, mutable_target, signature_buffer, MAX_SALT_LEN, MAX_VALUE_LEN,
+    PUBLIC_KEY_LEN, SIGNATURE_LEN,
+};
+
+/// The length of a node's id, which is in the same space as info-hashes
+pub const NODE_ID_LEN: usize = 20;
+
+/// The length of the compact node info of an IPv4 node
+pub const COMPACT_NODE_V4_LEN: usize = NODE_ID_LEN + compact::V4_LEN;
+
+/// The length of the compact node info of an IPv6 node
+pub const COMPACT_NODE_V6_LEN: usize = NODE_ID_LEN + compact::V6_LEN;
+
+/// A node's id
+pub type NodeId = [u8; NODE_ID_LEN];
+
+/// A node's id and address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeInfo {
+    pub id: NodeId,
+    pub addr: SocketAddr,
+}
+
+impl NodeInfo {
+    /// Decodes the compact node info of IPv4 nodes, as in `nodes`, or returns
+    /// `None` if the bytes aren't a whole number of them
+    #[must_use]
+    pub fn decode_v4(bytes: &[u8]) -> Option<Vec<Self>> {
+        decode_nodes(bytes, COMPACT_NODE_V4_LEN, compact::decode_v4)
+    }
+
+    /// Decodes the compact node info of IPv6 nodes, as in `nodes6`, or
+    /// returns `None` if the bytes aren't a whole number of them
+    #[must_use]
+    pub fn decode_v6(bytes: &[u8]) -> Option<Vec<Self>> {
+        decode_nodes(bytes, COMPACT_NODE_V6_LEN, compact::decode_v6)
+    }
+
+    /// Encodes the IPv4 nodes as compact node info, skipping any IPv6 nodes
+    pub fn encode_v4<'a, I>(nodes: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        encode_nodes(nodes, SocketAddr::is_ipv4)
+    }
+
+    /// Encodes the IPv6 nodes as compact node info, skipping any IPv4 nodes
+    pub fn encode_v6<'a, I>(nodes: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        encode_nodes(nodes, SocketAddr::is_ipv6)
+    }
+}
+
+fn decode_nodes(
+    bytes: &[u8],
+    len: usize,
+    decode_addr: fn(&[u8]) -> Option<Vec<SocketAddr>>,
+) -> Option<Vec<NodeInfo>> {
+    if bytes.len() % len != 0 {
+        return None;
+    }
+
+    bytes
+        .chunks_exact(len)
+        .map(|node| {
+            let (id, addr) = node.split_at(NODE_ID_LEN);
+            Some(NodeInfo {
+                id: id.try_into().ok()?,
+                addr: *decode_addr(addr)?.first()?,
+            })
+        })
+        .collect()
+}
+
+fn encode_nodes<'a, I>(nodes: I, include: fn(&SocketAddr) -> bool) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a NodeInfo>,
+{
+    let mut bytes = Vec::new();
+    for node in nodes.into_iter().filter(|node| include(&node.addr)) {
+        bytes.extend_from_slice(&node.id);
+        bytes.extend(compact::encode_v4(Some(&node.addr)));
+        bytes.extend(compact::encode_v6(Some(&node.addr)));
+    }
+    bytes
+}
@@ -0,0 +1,82 @@
+use sha1::{Digest, Sha1};
+
+use super::{NodeId, StorageError};
+use crate::RawBencode;
+
+/// The length of a mutable item's ed25519 public key
+pub const PUBLIC_KEY_LEN: usize = 32;
+
+/// The length of a mutable item's ed25519 signature
+pub const SIGNATURE_LEN: usize = 64;
+
+/// The most bytes an item's bencoded value may have
+pub const MAX_VALUE_LEN: usize = 1000;
+
+/// The most bytes a mutable item's salt may have
+pub const MAX_SALT_LEN: usize = 64;
+
+/// Returns the target of an immutable item, which is the SHA-1 hash of its
+/// bencoded value
+#[must_use]
+pub fn immutable_target(v: &RawBencode) -> NodeId {
+    Sha1::digest(v.as_bytes()).into()
+}
+
+/// Returns the target of a mutable item, which is the SHA-1 hash of its
+/// public key followed by its salt
+#[must_use]
+pub fn mutable_target(k: &[u8; PUBLIC_KEY_LEN], salt: &[u8]) -> NodeId {
+    Sha1::new()
+        .chain_update(k)
+        .chain_update(salt)
+        .finalize()
+        .into()
+}
+
+/// Returns the bytes a mutable item's signature is made over
+///
+/// These are the `salt`, if it isn't empty, `seq` and `v` entries of a
+/// bencoded dictionary, without the `d` and `e` around them, so `v` is kept
+/// exactly as it's encoded.
+///
+/// # Errors
+/// Fails if `v` is longer than [`MAX_VALUE_LEN`], or `salt` is longer than
+/// [`MAX_SALT_LEN`]
+///
+/// ```
+/// use bencoding::dht::signature_buffer;
+/// use bencoding::RawBencode;
+///
+/// let v = RawBencode::from_value(&"Hello World!").unwrap();
+///
+/// assert_eq!(
+///     b"4:salt6:foobar3:seqi1e1:v12:Hello World!",
+///     &signature_buffer(b"foobar", 1, &v).unwrap()[..],
+/// );
+/// ```
+pub fn signature_buffer(salt: &[u8], seq: i64, v: &RawBencode) -> Result<Vec<u8>, StorageError> {
+    check_lengths(salt, v)?;
+    let mut buffer = Vec::with_capacity(salt.len() + v.as_bytes().len() + 32);
+
+    if !salt.is_empty() {
+        buffer.extend_from_slice(b"4:salt");
+        buffer.extend_from_slice(salt.len().to_string().as_bytes());
+        buffer.push(b':');
+        buffer.extend_from_slice(salt);
+    }
+    buffer.extend_from_slice(format!("3:seqi{seq}e1:v").as_bytes());
+    buffer.extend_from_slice(v.as_bytes());
+
+    Ok(buffer)
+}
+
+// Checks an item against the limits nodes enforce on what they store
+pub(super) fn check_lengths(salt: &[u8], v: &RawBencode) -> Result<(), StorageError> {
+    if v.as_bytes().len() > MAX_VALUE_LEN {
+        return Err(StorageError::ValueTooLong(v.as_bytes().len()));
+    }
+    if salt.len() > MAX_SALT_LEN {
+        return Err(StorageError::SaltTooLong(salt.len()));
+    }
+    Ok(())
+}
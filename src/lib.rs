@@ -13,8 +13,25 @@
 //! assert_eq!("i5e", to_string(&example_int).unwrap()); // 5 is encoded as "i5e"
 //! ```
 
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
+pub mod config;
 pub mod de;
+pub mod dht;
+pub mod peer;
+mod percent;
 pub mod ser;
+pub mod torrent;
+pub mod tracker;
+pub mod value;
 
-pub use de::{from_bytes, from_str};
-pub use ser::to_string;
+pub use config::{BoolEncoding, Config, IntKeyEncoding, NoneEncoding, UnitEncoding};
+pub use de::{
+    from_bytes, from_bytes_prefix, from_bytes_with_config, from_bytes_with_options,
+    from_bytes_with_remainder, from_reader, from_str, DecoderOptions, StreamDeserializer,
+};
+pub use ser::{
+    to_canonical_vec, to_canonical_writer, to_string, to_vec, to_vec_with_config, to_writer,
+    to_writer_with_config,
+};
+pub use value::{from_value, to_value, to_value_with_config, RawBencode, Value, ValueRef};
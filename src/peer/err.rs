@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+use crate::de::BencodingDeserializeError;
+
+#[derive(Error, Debug)]
+pub enum MetadataError {
+    #[error("there is no info-hash to check the metadata against")]
+    MissingInfoHash,
+    #[error("metadata size {0} is not between 1 byte and 16 MiB")]
+    InvalidSize(u64),
+    #[error("piece {0} is past the end of the metadata")]
+    InvalidPiece(u32),
+    #[error("piece {piece} has {len} bytes, which is the wrong length")]
+    InvalidPieceLength { piece: u32, len: usize },
+    #[error("a peer says the metadata is {0} bytes, which doesn't match its size")]
+    SizeMismatch(u64),
+    #[error("the metadata doesn't match the info-hash")]
+    HashMismatch,
+    #[error("an error occurred while decoding the metadata: {0}")]
+    Deserialize(#[from] BencodingDeserializeError),
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+
+use crate::de::{from_bytes_with_remainder, BencodingDeserializeError};
+use crate::ser::{to_canonical_vec, BencodingSerializeError};
+
+/// The id of the peer-wire message that carries every extension message
+pub const EXTENDED_MESSAGE_ID: u8 = 20;
+
+/// The extended message id of the [`ExtendedHandshake`]
+pub const HANDSHAKE_ID: u8 = 0;
+
+/// The name of the metadata extension in an [`ExtendedHandshake`]'s `m`
+pub const UT_METADATA: &str = "ut_metadata";
+
+/// The length of every piece of metadata but the last
+pub const METADATA_PIECE_LEN: usize = 16 * 1024;
+
+/// The first message of the extension protocol, as described by BEP 10,
+/// saying which extensions a peer supports
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedHandshake {
+    /// The extended message id the peer wants for each extension it
+    /// supports, from `m`, where an id of 0 disables the extension
+    #[serde(rename = "m", default)]
+    pub extensions: BTreeMap<String, u8>,
+    /// The peer's client name and version, from `v`
+    #[serde(rename = "v", skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// The port the peer is listening on, from `p`
+    #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// The number of outstanding requests the peer allows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reqq: Option<u64>,
+    /// The length of the torrent's `info` dictionary, sent by peers that have
+    /// it and support [`UT_METADATA`], as described by BEP 9
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_size: Option<u64>,
+    /// The address the peer sees this client connecting from, from `yourip`
+    #[serde(
+        rename = "yourip",
+        with = "compact_ip",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub your_ip: Option<IpAddr>,
+}
+
+impl ExtendedHandshake {
+    /// Returns the extended message id the peer wants for an extension, or
+    /// `None` if it doesn't support it
+    #[must_use]
+    pub fn extension_id(&self, name: &str) -> Option<u8> {
+        self.extensions.get(name).copied().filter(|&id| id != 0)
+    }
+}
+
+// `yourip` is an IPv4 or IPv6 address in network byte order
+mod compact_ip {
+    use std::convert::TryFrom;
+    use std::net::IpAddr;
+
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_bytes::ByteBuf;
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S>(ip: &Option<IpAddr>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match ip {
+            Some(IpAddr::V4(ip)) => serializer.serialize_bytes(&ip.octets()),
+            Some(IpAddr::V6(ip)) => serializer.serialize_bytes(&ip.octets()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<IpAddr>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+
+        match bytes.len() {
+            4 => <[u8; 4]>::try_from(&bytes[..]).map(IpAddr::from),
+            16 => <[u8; 16]>::try_from(&bytes[..]).map(IpAddr::from),
+            len => return Err(D::Error::invalid_length(len, &"an IPv4 or IPv6 address")),
+        }
+        .map(Some)
+        .map_err(D::Error::custom)
+    }
+}
+
+/// A message of the metadata extension, as described by BEP 9, which lets a
+/// client with only a magnet link fetch the `info` dictionary from peers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UtMetadata {
+    /// Asks for a piece of the metadata
+    Request { piece: u32 },
+    /// A piece of the metadata, which follows the dictionary in the message
+    Data {
+        piece: u32,
+        /// The length of the whole `info` dictionary
+        total_size: u64,
+        data: Vec<u8>,
+    },
+    /// Refuses a request for a piece
+    Reject { piece: u32 },
+}
+
+// The dictionary at the start of every metadata message
+#[derive(Serialize, Deserialize)]
+struct UtMetadataFields {
+    msg_type: u8,
+    piece: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_size: Option<u64>,
+}
+
+impl UtMetadata {
+    /// Returns the index of the piece the message is about
+    #[must_use]
+    pub const fn piece(&self) -> u32 {
+        match self {
+            Self::Request { piece } | Self::Data { piece, .. } | Self::Reject { piece } => *piece,
+        }
+    }
+
+    /// Decodes a message from the payload of an extended message, after its
+    /// extended message id
+    ///
+    /// # Errors
+    /// Fails if the dictionary can't be decoded, if its `msg_type` is
+    /// unknown, or if anything but a data message has bytes after it
+    pub fn decode(payload: &[u8]) -> Result<Self, BencodingDeserializeError> {
+        let (fields, rest) = from_bytes_with_remainder::<UtMetadataFields>(payload)?;
+        let piece = fields.piece;
+
+        let message = match fields.msg_type {
+            0 => Self::Request { piece },
+            1 => Self::Data {
+                piece,
+                total_size: fields
+                    .total_size
+                    .ok_or_else(|| BencodingDeserializeError::missing_field("total_size"))?,
+                data: rest.to_vec(),
+            },
+            2 => Self::Reject { piece },
+            msg_type => {
+                return Err(BencodingDeserializeError::custom(format!(
+                    "unknown metadata message type {msg_type}"
+                )))
+            }
+        };
+
+        if !rest.is_empty() && !matches!(message, Self::Data { .. }) {
+            return Err(BencodingDeserializeError::TrailingCharacters);
+        }
+
+        Ok(message)
+    }
+
+    /// Encodes the message as the payload of an extended message, with a
+    /// data message's piece after its dictionary
+    ///
+    /// # Errors
+    /// Fails if encoding the dictionary fails
+    pub fn encode(&self) -> Result<Vec<u8>, BencodingSerializeError> {
+        let (msg_type, total_size, data) = match self {
+            Self::Request { .. } => (0, None, &[][..]),
+            Self::Data {
+                total_size, data, ..
+            } => (1, Some(*total_size), &data[..]),
+            Self::Reject { .. } => (2, None, &[][..]),
+        };
+
+        let mut payload = to_canonical_vec(&UtMetadataFields {
+            msg_type,
+            piece: self.piece(),
+            total_size,
+        })?;
+        payload.extend_from_slice(data);
+
+        Ok(payload)
+    }
+}
@@ -0,0 +1,192 @@
+use std::convert::TryFrom;
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use super::{MetadataError, UtMetadata, METADATA_PIECE_LEN};
+use crate::torrent::{MagnetLink, Metainfo, MERKLE_HASH_LEN, PIECE_HASH_LEN};
+use crate::RawBencode;
+
+/// The largest `metadata_size` a peer may give
+pub const MAX_METADATA_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Assembles a torrent's `info` dictionary from the pieces of metadata sent by
+/// peers, as described by BEP 9
+///
+/// Pieces may come from any number of peers in any order. Once every piece
+/// has arrived, the metadata is checked against the info-hashes and decoded
+/// into a [`Metainfo`] that keeps the exact bytes, so its info-hashes are the
+/// ones that were checked. Only its `info` is filled in.
+/// If it doesn't match, every piece is thrown away, as there's no telling
+/// which peer sent a bad one.
+///
+/// ```
+/// use bencoding::peer::{MetadataAssembler, UtMetadata};
+/// use bencoding::torrent::MagnetLink;
+///
+/// let info = b"d6:lengthi5e4:name5:a.txt12:piece lengthi16384e6:pieces0:e";
+/// let link: MagnetLink = "magnet:?xt=urn:btih:e70821096597415c57ae25f765693641fa627fac"
+///     .parse()
+///     .unwrap();
+///
+/// let mut assembler = MetadataAssembler::new(&link, info.len() as u64).unwrap();
+/// assert_eq!(
+///     vec![UtMetadata::Request { piece: 0 }],
+///     assembler.requests().collect::<Vec<_>>(),
+/// );
+///
+/// let torrent = assembler.add_piece(0, info).unwrap().unwrap();
+/// assert_eq!("a.txt", torrent.info().name);
+/// assert_eq!(link.info_hash_v1, Some(torrent.info_hash_v1()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetadataAssembler {
+    info_hash_v1: Option<[u8; PIECE_HASH_LEN]>,
+    info_hash_v2: Option<[u8; MERKLE_HASH_LEN]>,
+    metadata: Vec<u8>,
+    received: Vec<bool>,
+}
+
+impl MetadataAssembler {
+    /// Creates an assembler for metadata of the given size, as sent in a
+    /// peer's extension handshake, which is checked against the magnet
+    /// link's info-hashes
+    ///
+    /// # Errors
+    /// Fails if the magnet link has no info-hash, or if the size is zero or
+    /// more than [`MAX_METADATA_SIZE`]
+    pub fn new(magnet: &MagnetLink, metadata_size: u64) -> Result<Self, MetadataError> {
+        if magnet.info_hash_v1.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(MetadataError::MissingInfoHash);
+        }
+        if metadata_size == 0 || metadata_size > MAX_METADATA_SIZE {
+            return Err(MetadataError::InvalidSize(metadata_size));
+        }
+        let len = usize::try_from(metadata_size)
+            .map_err(|_| MetadataError::InvalidSize(metadata_size))?;
+
+        Ok(Self {
+            info_hash_v1: magnet.info_hash_v1,
+            info_hash_v2: magnet.info_hash_v2,
+            metadata: vec![0; len],
+            received: vec![false; len.div_ceil(METADATA_PIECE_LEN)],
+        })
+    }
+
+    /// Returns the length of the whole `info` dictionary
+    #[must_use]
+    pub fn metadata_size(&self) -> u64 {
+        self.metadata.len() as u64
+    }
+
+    /// Returns the number of pieces the metadata is split into
+    #[must_use]
+    pub fn piece_count(&self) -> usize {
+        self.received.len()
+    }
+
+    /// Returns whether every piece has arrived and been checked
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.received.iter().all(|&received| received)
+    }
+
+    /// Returns the indices of the pieces that haven't arrived
+    pub fn missing_pieces(&self) -> impl Iterator<Item = u32> + '_ {
+        self.received
+            .iter()
+            .zip(0..)
+            .filter(|(&received, _)| !received)
+            .map(|(_, piece)| piece)
+    }
+
+    /// Returns a request for each piece that hasn't arrived
+    pub fn requests(&self) -> impl Iterator<Item = UtMetadata> + '_ {
+        self.missing_pieces()
+            .map(|piece| UtMetadata::Request { piece })
+    }
+
+    /// Adds a piece of the metadata, returning the torrent with the decoded
+    /// `info` dictionary if it was the last one missing
+    ///
+    /// A piece that has already arrived is ignored.
+    ///
+    /// # Errors
+    /// Fails if the piece isn't part of the metadata or is the wrong length,
+    /// or if it completes metadata that doesn't match the info-hashes or
+    /// can't be decoded, in which case every piece is thrown away
+    pub fn add_piece(
+        &mut self,
+        piece: u32,
+        data: &[u8],
+    ) -> Result<Option<Metainfo>, MetadataError> {
+        let index = usize::try_from(piece)
+            .ok()
+            .filter(|&index| index < self.piece_count())
+            .ok_or(MetadataError::InvalidPiece(piece))?;
+
+        let start = index * METADATA_PIECE_LEN;
+        let end = self.metadata.len().min(start + METADATA_PIECE_LEN);
+        if data.len() != end - start {
+            return Err(MetadataError::InvalidPieceLength {
+                piece,
+                len: data.len(),
+            });
+        }
+
+        if self.received[index] {
+            return Ok(None);
+        }
+        self.metadata[start..end].copy_from_slice(data);
+        self.received[index] = true;
+
+        if !self.is_complete() {
+            return Ok(None);
+        }
+
+        let torrent = self.finish();
+        if torrent.is_err() {
+            self.received.fill(false);
+        }
+        torrent.map(Some)
+    }
+
+    /// Adds the piece in a data message, as [`add_piece`](Self::add_piece)
+    /// does, ignoring any other message
+    ///
+    /// # Errors
+    /// Fails if the message's `total_size` isn't the size of the metadata,
+    /// or if adding its piece fails
+    pub fn receive(&mut self, message: &UtMetadata) -> Result<Option<Metainfo>, MetadataError> {
+        if let UtMetadata::Data {
+            piece,
+            total_size,
+            data,
+        } = message
+        {
+            if *total_size != self.metadata_size() {
+                return Err(MetadataError::SizeMismatch(*total_size));
+            }
+            self.add_piece(*piece, data)
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Checks the whole metadata against the info-hashes, and decodes it
+    fn finish(&self) -> Result<Metainfo, MetadataError> {
+        let v1_matches = self
+            .info_hash_v1
+            .map_or(true, |hash| Sha1::digest(&self.metadata)[..] == hash[..]);
+        let v2_matches = self
+            .info_hash_v2
+            .map_or(true, |hash| Sha256::digest(&self.metadata)[..] == hash[..]);
+        if !v1_matches || !v2_matches {
+            return Err(MetadataError::HashMismatch);
+        }
+
+        let info_bytes = RawBencode::from_bytes(self.metadata.clone())?;
+
+        Ok(Metainfo::from_info_bytes(info_bytes)?)
+    }
+}
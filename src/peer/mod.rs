@@ -0,0 +1,20 @@
+//! Messages of the peer-wire extension protocol, as described by BEP 10,
+//! which are bencoded dictionaries that may be followed by a raw payload.
+//!
+//! A client that only has a magnet link fetches the torrent's `info`
+//! dictionary from peers with [`UtMetadata`] messages, putting the pieces
+//! together with a [`MetadataAssembler`]. Peers tell each other about other
+//! peers of the torrent with [`PexMessage`]s.
+
+mod err;
+mod extension;
+mod metadata;
+mod pex;
+
+pub use err::MetadataError;
+pub use extension::{
+    ExtendedHandshake, UtMetadata, EXTENDED_MESSAGE_ID, HANDSHAKE_ID, METADATA_PIECE_LEN,
+    UT_METADATA,
+};
+pub use metadata::{MetadataAssembler, MAX_METADATA_SIZE};
+pub use pex::{PexFlags, PexMessage, PexPeer, UT_PEX};
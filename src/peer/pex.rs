@@ -0,0 +1,175 @@
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::ops::{BitOr, BitOrAssign};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tracker::compact;
+
+/// The name of the peer exchange extension in an
+/// [`ExtendedHandshake`](super::ExtendedHandshake)'s `m`
+pub const UT_PEX: &str = "ut_pex";
+
+/// What's known about a peer in a [`PexMessage`], from `added.f` and
+/// `added6.f`, which have a byte of flags for each added peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PexFlags(pub u8);
+
+impl PexFlags {
+    /// The peer prefers encrypted connections
+    pub const ENCRYPTION: Self = Self(0x01);
+    /// The peer is a seed, or only uploading
+    pub const SEED: Self = Self(0x02);
+    /// The peer supports uTP
+    pub const UTP: Self = Self(0x04);
+    /// The peer supports the holepunch extension
+    pub const HOLEPUNCH: Self = Self(0x08);
+    /// The peer accepted incoming connections, so is reachable
+    pub const REACHABLE: Self = Self(0x10);
+
+    /// Returns whether every flag in `other` is set
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for PexFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for PexFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A peer added in a [`PexMessage`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PexPeer {
+    pub addr: SocketAddr,
+    pub flags: PexFlags,
+}
+
+impl PexPeer {
+    #[must_use]
+    pub const fn new(addr: SocketAddr, flags: PexFlags) -> Self {
+        Self { addr, flags }
+    }
+}
+
+/// A peer exchange message, as described by BEP 11, listing the peers that
+/// have been connected to or disconnected from since the last one
+///
+/// Peers are sent in the compact format, so IPv4 peers belong in `added` and
+/// `dropped`, and IPv6 peers in `added6` and `dropped6`. Any peer in the
+/// wrong list is skipped when encoding.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "PexFields", into = "PexFields")]
+pub struct PexMessage {
+    pub added: Vec<PexPeer>,
+    pub dropped: Vec<SocketAddr>,
+    pub added6: Vec<PexPeer>,
+    pub dropped6: Vec<SocketAddr>,
+}
+
+impl PexMessage {
+    /// Returns whether the message has no peers
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.dropped.is_empty()
+            && self.added6.is_empty()
+            && self.dropped6.is_empty()
+    }
+}
+
+// Every key is written, even with no peers, as clients do
+#[derive(Serialize, Deserialize)]
+struct PexFields {
+    #[serde(with = "serde_bytes", default)]
+    added: Vec<u8>,
+    #[serde(rename = "added.f", with = "serde_bytes", default)]
+    added_flags: Vec<u8>,
+    #[serde(with = "serde_bytes", default)]
+    added6: Vec<u8>,
+    #[serde(rename = "added6.f", with = "serde_bytes", default)]
+    added6_flags: Vec<u8>,
+    #[serde(with = "serde_bytes", default)]
+    dropped: Vec<u8>,
+    #[serde(with = "serde_bytes", default)]
+    dropped6: Vec<u8>,
+}
+
+impl TryFrom<PexFields> for PexMessage {
+    type Error = String;
+
+    fn try_from(fields: PexFields) -> Result<Self, Self::Error> {
+        let v4 = |key: &str, bytes: &[u8]| {
+            compact::decode_v4(bytes)
+                .ok_or_else(|| format!("'{key}' is not a whole number of compact IPv4 peers"))
+        };
+        let v6 = |key: &str, bytes: &[u8]| {
+            compact::decode_v6(bytes)
+                .ok_or_else(|| format!("'{key}' is not a whole number of compact IPv6 peers"))
+        };
+
+        Ok(Self {
+            added: with_flags("added.f", v4("added", &fields.added)?, &fields.added_flags)?,
+            dropped: v4("dropped", &fields.dropped)?,
+            added6: with_flags(
+                "added6.f",
+                v6("added6", &fields.added6)?,
+                &fields.added6_flags,
+            )?,
+            dropped6: v6("dropped6", &fields.dropped6)?,
+        })
+    }
+}
+
+impl From<PexMessage> for PexFields {
+    fn from(message: PexMessage) -> Self {
+        let (v4, v4_flags) = split_flags(&message.added, SocketAddr::is_ipv4);
+        let (v6, v6_flags) = split_flags(&message.added6, SocketAddr::is_ipv6);
+
+        Self {
+            added: compact::encode_v4(&v4),
+            added_flags: v4_flags,
+            added6: compact::encode_v6(&v6),
+            added6_flags: v6_flags,
+            dropped: compact::encode_v4(&message.dropped),
+            dropped6: compact::encode_v6(&message.dropped6),
+        }
+    }
+}
+
+// Pairs each peer with its flags, which some clients leave out entirely
+fn with_flags(key: &str, addrs: Vec<SocketAddr>, flags: &[u8]) -> Result<Vec<PexPeer>, String> {
+    if !flags.is_empty() && flags.len() != addrs.len() {
+        return Err(format!(
+            "'{key}' has {} flags for {} peers",
+            flags.len(),
+            addrs.len()
+        ));
+    }
+
+    let flags = flags.iter().copied().chain(std::iter::repeat(0));
+    Ok(addrs
+        .into_iter()
+        .zip(flags)
+        .map(|(addr, flags)| PexPeer::new(addr, PexFlags(flags)))
+        .collect())
+}
+
+// Separates the addresses of the peers to include from their flags
+fn split_flags(peers: &[PexPeer], include: fn(&SocketAddr) -> bool) -> (Vec<SocketAddr>, Vec<u8>) {
+    peers
+        .iter()
+        .filter(|peer| include(&peer.addr))
+        .map(|peer| (peer.addr, peer.flags.0))
+        .unzip()
+}
@@ -0,0 +1,53 @@
+//! Percent-encoding for magnet links and tracker query strings, which can hold
+//! arbitrary bytes such as raw info-hashes.
+
+use std::fmt::Write;
+
+/// Decodes `%XX` escapes, leaving every other byte as it is, or returns
+/// `None` if an escape is invalid. Binary values such as info-hashes are
+/// decoded this way, as a `+` in them is just a byte.
+pub fn decode(s: &str) -> Option<Vec<u8>> {
+    decode_with(s, false)
+}
+
+/// Decodes `%XX` escapes, and `+` as a space as in a query string, or returns
+/// `None` if an escape is invalid
+pub fn decode_query(s: &str) -> Option<Vec<u8>> {
+    decode_with(s, true)
+}
+
+fn decode_with(s: &str, plus_as_space: bool) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut input = s.bytes();
+
+    while let Some(byte) = input.next() {
+        match byte {
+            b'%' => {
+                // `from_str_radix` would also accept a sign, such as `%+1`
+                let hex = [input.next()?, input.next()?];
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            b'+' if plus_as_space => bytes.push(b' '),
+            byte => bytes.push(byte),
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Escapes everything but the characters that RFC 3986 leaves unreserved
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
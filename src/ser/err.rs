@@ -5,6 +5,16 @@ use thiserror::Error;
 pub enum BencodingSerializeError {
     #[error("{0}")]
     SerdeSerializeError(String),
+    #[error("an error occurred while writing the output: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("type '{0}' not supported")]
+    UnsupportedType(&'static str),
+    #[error("dictionary keys must be byte strings")]
+    NonStringKey,
+    #[error("dictionary key '{0}' appears more than once")]
+    DuplicateKey(String),
+    #[error("the output contains a byte string that isn't valid UTF-8")]
+    OutputNotUtf8,
 }
 
 impl serde::ser::Error for BencodingSerializeError {
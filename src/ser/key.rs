@@ -0,0 +1,191 @@
+use serde::ser::{self, Impossible, Serialize};
+
+use super::{BencodingSerializeError, Result};
+use crate::config::{Config, IntKeyEncoding};
+
+// Serializes a dictionary key to its raw bytes. Bencode only allows byte
+// strings as keys, so anything that isn't string-like is rejected rather than
+// producing invalid bencode, apart from integers when the config allows them
+// to be written as their decimal digits.
+pub struct MapKeySerializer {
+    config: Config,
+}
+
+impl MapKeySerializer {
+    pub const fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn serialize_integer<T>(self, v: &T) -> Result<Vec<u8>>
+    where
+        T: ToString,
+    {
+        match self.config.int_key_encoding {
+            IntKeyEncoding::Error => Err(BencodingSerializeError::NonStringKey),
+            IntKeyEncoding::String => Ok(v.to_string().into_bytes()),
+        }
+    }
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = Vec<u8>;
+    type Error = BencodingSerializeError;
+
+    type SerializeSeq = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeTuple = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeMap = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeStruct = Impossible<Vec<u8>, BencodingSerializeError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, BencodingSerializeError>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Vec<u8>> {
+        self.serialize_integer(&v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Vec<u8>> {
+        Ok(v.encode_utf8(&mut [0; 4]).as_bytes().to_vec())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Vec<u8>> {
+        Ok(v.as_bytes().to_vec())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>> {
+        Ok(v.to_vec())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<u8>> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    // Unit variants are written as their name, so fieldless enums can be used
+    // as keys
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Vec<u8>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(BencodingSerializeError::NonStringKey)
+    }
+}
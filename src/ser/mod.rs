@@ -1,32 +1,217 @@
+use std::io;
+
 use serde::{ser, Serialize};
 
+use crate::config::{BoolEncoding, Config, NoneEncoding, UnitEncoding};
+use crate::value::{raw_bytes, RAW_TOKEN};
+
 mod err;
+mod key;
 
 pub use err::BencodingSerializeError;
+pub(crate) use key::MapKeySerializer;
 type Result<T> = std::result::Result<T, BencodingSerializeError>;
 
-pub struct Serializer {
-    // This string starts empty and bencode is appended as values are serialized.
-    output: String,
+pub struct Serializer<W> {
+    // Bencode is written to this sink as values are serialized.
+    writer: W,
+    // Whether dictionaries are written sorted by key, as the specification
+    // requires, rather than in the order their entries are serialized
+    canonical: bool,
+    config: Config,
+    // The encoded key of a dictionary entry whose value hasn't been written
+    // yet. It's only written along with the value, so that the whole entry
+    // can be left out if the value is an omitted `None`.
+    pending_key: Option<Vec<u8>>,
+}
+
+impl<W> Serializer<W>
+where
+    W: io::Write,
+{
+    /// Creates a new serializer that writes bencode to the given sink
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            canonical: false,
+            config: Config::new(),
+            pending_key: None,
+        }
+    }
+
+    /// Creates a new serializer that writes canonical bencode to the given
+    /// sink, with every dictionary sorted by the raw bytes of its keys
+    pub const fn canonical(writer: W) -> Self {
+        Self {
+            writer,
+            canonical: true,
+            config: Config::new(),
+            pending_key: None,
+        }
+    }
+
+    /// Sets how types that bencode has no equivalent for are encoded
+    ///
+    /// This can be combined with either way of making a serializer.
+    /// ```
+    /// use bencoding::ser::Serializer;
+    /// use bencoding::{BoolEncoding, Config};
+    /// use serde::Serialize;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let config = Config {
+    ///     bool_encoding: BoolEncoding::Int,
+    ///     ..Config::new()
+    /// };
+    /// let mut serializer = Serializer::canonical(Vec::new()).with_config(config);
+    ///
+    /// BTreeMap::from([("seed", true), ("private", false)])
+    ///     .serialize(&mut serializer)
+    ///     .unwrap();
+    /// assert_eq!(b"d7:privatei0e4:seedi1ee".to_vec(), serializer.into_inner());
+    /// ```
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Unwraps the serializer, returning the underlying sink
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Returns the sink, having first written any pending dictionary key
+    fn writer(&mut self) -> io::Result<&mut W> {
+        if let Some(key) = self.pending_key.take() {
+            self.writer.write_all(&key)?;
+        }
+        Ok(&mut self.writer)
+    }
+
+    /// Writes a byte string in the form `<len>:<bytes>`
+    fn write_byte_string(&mut self, v: &[u8]) -> Result<()> {
+        let writer = self.writer()?;
+        write!(writer, "{}:", v.len())?;
+        writer.write_all(v)?;
+        Ok(())
+    }
+
+    /// Encodes a dictionary value on it's own, canonically and with the same
+    /// config, so that it can be buffered. A value that is left out is
+    /// encoded as nothing at all.
+    fn encode_dict_value<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::canonical(Vec::new()).with_config(self.config);
+        // An empty pending key marks the value as being in a dictionary
+        serializer.pending_key = Some(Vec::new());
+        value.serialize(&mut serializer)?;
+        Ok(serializer.into_inner())
+    }
+}
+
+/// Writes the bencoded version of a value to the given sink
+///
+/// # Errors
+/// Fails if serialization fails, or if writing to the sink fails
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(writer);
+    value.serialize(&mut serializer)
+}
+
+/// Writes the bencoded version of a value to the given sink, encoding types
+/// that bencode has no equivalent for according to the given config
+///
+/// # Errors
+/// Fails if serialization fails, or if writing to the sink fails
+pub fn to_writer_with_config<W, T>(writer: W, value: &T, config: Config) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(writer).with_config(config);
+    value.serialize(&mut serializer)
+}
+
+/// Converts a value to it's bencoded version as raw bytes
+///
+/// # Errors
+/// Fails if serialization fails
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut output = Vec::new();
+    to_writer(&mut output, value)?;
+    Ok(output)
+}
+
+/// Converts a value to it's bencoded version as raw bytes, encoding types
+/// that bencode has no equivalent for according to the given config
+///
+/// # Errors
+/// Fails if serialization fails
+pub fn to_vec_with_config<T>(value: &T, config: Config) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut output = Vec::new();
+    to_writer_with_config(&mut output, value, config)?;
+    Ok(output)
+}
+
+/// Writes the canonical bencoded version of a value to the given sink, with
+/// every dictionary sorted by the raw bytes of its keys
+///
+/// # Errors
+/// Fails if serialization fails, if a dictionary contains the same key more
+/// than once, or if writing to the sink fails
+pub fn to_canonical_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::canonical(writer);
+    value.serialize(&mut serializer)
+}
+
+/// Converts a value to it's canonical bencoded version as raw bytes, with
+/// every dictionary sorted by the raw bytes of its keys
+///
+/// # Errors
+/// Fails if serialization fails, or if a dictionary contains the same key
+/// more than once
+pub fn to_canonical_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut output = Vec::new();
+    to_canonical_writer(&mut output, value)?;
+    Ok(output)
 }
 
 /// Converts a value to it's bencoded version
 ///
 /// # Errors
-/// fails if serialization fails
+/// Fails if serialization fails, or if the bencoded value contains a byte
+/// string that isn't valid UTF-8
 pub fn to_string<T>(value: &T) -> Result<String>
 where
-    T: Serialize,
+    T: ?Sized + Serialize,
 {
-    let mut serializer = Serializer {
-        output: String::new(),
-    };
-
-    value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    String::from_utf8(to_vec(value)?).map_err(|_| BencodingSerializeError::OutputNotUtf8)
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<'a, W> ser::Serializer for &'a mut Serializer<W>
+where
+    W: io::Write,
+{
     // The output type produced by this `Serializer` during successful
     // serialization. Most serializers that produce text or binary output should
     // set `Ok = ()` and serialize into an `io::Write` or buffer contained
@@ -46,12 +231,17 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
-
-    fn serialize_bool(self, _v: bool) -> Result<()> {
-        panic!("This version of bencoding doesn't support bools");
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    // Bencode has no booleans, but they can be written as the integers 0 and
+    // 1 if the config allows it
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        match self.config.bool_encoding {
+            BoolEncoding::Error => Err(BencodingSerializeError::UnsupportedType("bool")),
+            BoolEncoding::Int => self.serialize_i64(i64::from(v)),
+        }
     }
 
     // Bencode does not distinguish between different sizes of integers, so all
@@ -71,7 +261,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.output += &format!("i{}e", v);
+        write!(self.writer()?, "i{v}e")?;
         Ok(())
     }
 
@@ -88,53 +278,47 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output += &format!("i{}e", v);
+        write!(self.writer()?, "i{v}e")?;
         Ok(())
     }
 
-    fn serialize_f32(self, v: f32) -> Result<()> {
-        self.serialize_f64(f64::from(v))
+    // Bencode only has integers, and there's no lossless way to write a float
+    // as one
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(BencodingSerializeError::UnsupportedType("f32"))
     }
 
-    fn serialize_f64(self, v: f64) -> Result<()> {
-        self.output += &format!("i{}e", v);
-        Ok(())
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(BencodingSerializeError::UnsupportedType("f64"))
     }
 
     // Serialize a char as a single-character string. Other formats may
     // represent this differently.
     fn serialize_char(self, v: char) -> Result<()> {
-        self.output += &format!("1:{}", v);
-        Ok(())
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.output += &format!("{}:{}", v.len(), v);
-        Ok(())
+        self.write_byte_string(v.as_bytes())
     }
 
-    // Serialize a byte array as an array of bytes. Could also use a base64
-    // string here. Binary formats will typically represent byte arrays more
-    // compactly.
+    // Bencode strings are arbitrary byte strings, so a byte array is written
+    // as `<len>:` followed by the raw bytes themselves.
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        use serde::ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
-        }
-        seq.end()
+        self.write_byte_string(v)
     }
 
-    // An absent optional is represented as the bencode `null`.
+    // Bencode has no null, so there's nothing an absent optional can be
+    // written as. The config can allow a dictionary entry with no value to be
+    // left out though, by dropping its key before it's written.
     fn serialize_none(self) -> Result<()> {
-        self.serialize_unit()
+        match self.config.none_encoding {
+            NoneEncoding::Omit if self.pending_key.take().is_some() => Ok(()),
+            _ => Err(BencodingSerializeError::UnsupportedType("none")),
+        }
     }
 
-    // A present optional is represented as just the contained value. Note that
-    // this is a lossy representation. For example the values `Some(())` and
-    // `None` both serialize as just `null`. Unfortunately this is typically
-    // what people expect when working with bencode. Other formats are encouraged
-    // to behave more intelligently if possible.
+    // A present optional is represented as just the contained value.
     fn serialize_some<T>(self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
@@ -142,17 +326,23 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         value.serialize(self)
     }
 
-    // In Serde, unit means an anonymous value containing no data. Map this to
-    // bencode as `null`.
+    // In Serde, unit means an anonymous value containing no data. Bencode has
+    // no null to map this to, unless the config allows it to be written as an
+    // empty byte string.
     fn serialize_unit(self) -> Result<()> {
-        panic!("Units are not supported in this version of bencoding");
+        match self.config.unit_encoding {
+            UnitEncoding::Error => Err(BencodingSerializeError::UnsupportedType("unit")),
+            UnitEncoding::EmptyString => self.write_byte_string(b""),
+        }
     }
 
-    // Unit struct means a named value containing no data. Again, since there is
-    // no data, map this to bencode as `null`. There is no need to serialize the
-    // name in most formats.
+    // Unit struct means a named value containing no data, which is written
+    // the same way.
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        self.serialize_unit()
+        match self.config.unit_encoding {
+            UnitEncoding::Error => Err(BencodingSerializeError::UnsupportedType("unit struct")),
+            UnitEncoding::EmptyString => self.write_byte_string(b""),
+        }
     }
 
     // When serializing a unit variant (or any other kind of variant), formats
@@ -169,11 +359,19 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     // As is done here, serializers are encouraged to treat newtype structs as
-    // insignificant wrappers around the data they contain.
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    // insignificant wrappers around the data they contain. The exception is
+    // `RawBencode`, whose bytes are already bencode and are written as they
+    // are.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        if name == RAW_TOKEN {
+            let bytes = raw_bytes(value)?;
+            self.writer()?.write_all(&bytes)?;
+            return Ok(());
+        }
+
         value.serialize(self)
     }
 
@@ -192,8 +390,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
+        self.writer()?.write_all(b"d")?;
         variant.serialize(&mut *self)?;
         value.serialize(&mut *self)?;
+        self.writer()?.write_all(b"e")?;
         Ok(())
     }
 
@@ -208,7 +408,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // explicitly in the serialized form. Some serializers may only be able to
     // support sequences for which the length is known up front.
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.output += "l";
+        self.writer()?.write_all(b"l")?;
         Ok(self)
     }
 
@@ -235,16 +435,19 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        panic!("Tuples are not supported in this version of bencoding");
+        self.writer()?.write_all(b"d")?;
+        variant.serialize(&mut *self)?;
+        self.writer()?.write_all(b"l")?;
+        Ok(self)
     }
 
     // Maps are represented in bencode as `{ K: V, K: V, ... }`.
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.output += "d";
-        Ok(self)
+        self.writer()?.write_all(b"d")?;
+        Ok(Compound::new(self))
     }
 
     // Structs look just like maps in bencode. In particular, bencode requires that we
@@ -265,10 +468,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.output += "d";
+        self.writer()?.write_all(b"d")?;
         variant.serialize(&mut *self)?;
-        self.output += "d";
-        Ok(self)
+        self.writer()?.write_all(b"d")?;
+        Ok(Compound::new(self))
     }
 }
 
@@ -279,7 +482,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<W> ser::SerializeSeq for &mut Serializer<W>
+where
+    W: io::Write,
+{
     type Ok = ();
 
     type Error = BencodingSerializeError;
@@ -294,13 +500,16 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
 
     // Close the sequence.
     fn end(self) -> Result<()> {
-        self.output += "e";
+        self.writer()?.write_all(b"e")?;
         Ok(())
     }
 }
 
 // Same thing but for tuples.
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<W> ser::SerializeTuple for &mut Serializer<W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
@@ -312,13 +521,16 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        self.output += "e";
+        self.writer()?.write_all(b"e")?;
         Ok(())
     }
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<W> ser::SerializeTupleStruct for &mut Serializer<W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
@@ -330,7 +542,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        self.output += "e";
+        self.writer()?.write_all(b"e")?;
         Ok(())
     }
 }
@@ -338,13 +550,16 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
 // Tuple variants are a little different. Refer back to the
 // `serialize_tuple_variant` method above:
 //
-//    self.output += "{";
+//    self.writer()?.write_all(b"d")?;
 //    variant.serialize(&mut *self)?;
-//    self.output += ":[";
+//    self.writer()?.write_all(b"l")?;
 //
-// So the `end` method in this impl is responsible for closing both the `]` and
-// the `}`.
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+// So the `end` method in this impl is responsible for closing both the list
+// and the dictionary.
+impl<W> ser::SerializeTupleVariant for &mut Serializer<W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
@@ -356,7 +571,108 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        self.output += "ee";
+        self.writer()?.write_all(b"ee")?;
+        Ok(())
+    }
+}
+
+// Dictionaries are serialized through `Compound`, which either writes each
+// entry straight to the output, or when serializing canonically buffers every
+// entry so they can be written sorted by the raw bytes of their keys once the
+// dictionary ends.
+pub enum Compound<'a, W> {
+    Direct(&'a mut Serializer<W>),
+    Sorted {
+        ser: &'a mut Serializer<W>,
+        // Pairs of raw key bytes and the encoded value
+        entries: Vec<(Vec<u8>, Vec<u8>)>,
+        next_key: Option<Vec<u8>>,
+    },
+}
+
+impl<'a, W> Compound<'a, W>
+where
+    W: io::Write,
+{
+    const fn new(ser: &'a mut Serializer<W>) -> Self {
+        if ser.canonical {
+            Self::Sorted {
+                ser,
+                entries: Vec::new(),
+                next_key: None,
+            }
+        } else {
+            Self::Direct(ser)
+        }
+    }
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self {
+            Self::Direct(ser) => {
+                let key = key.serialize(MapKeySerializer::new(ser.config))?;
+                let mut encoded = format!("{}:", key.len()).into_bytes();
+                encoded.extend_from_slice(&key);
+                ser.pending_key = Some(encoded);
+            }
+            Self::Sorted { ser, next_key, .. } => {
+                *next_key = Some(key.serialize(MapKeySerializer::new(ser.config))?);
+            }
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self {
+            Self::Direct(ser) => value.serialize(&mut **ser),
+            Self::Sorted {
+                ser,
+                entries,
+                next_key,
+            } => {
+                let key = next_key.take().ok_or_else(|| {
+                    <BencodingSerializeError as ser::Error>::custom(
+                        "serialize_value called before serialize_key",
+                    )
+                })?;
+                let value = ser.encode_dict_value(value)?;
+                // Nothing is encoded for a value that is left out
+                if !value.is_empty() {
+                    entries.push((key, value));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Writes any buffered entries, followed by the given closing symbols
+    fn end(self, closing: &[u8]) -> Result<()> {
+        let ser = match self {
+            Self::Direct(ser) => ser,
+            Self::Sorted {
+                ser, mut entries, ..
+            } => {
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    return Err(BencodingSerializeError::DuplicateKey(
+                        String::from_utf8_lossy(&pair[0].0).into_owned(),
+                    ));
+                }
+
+                for (key, value) in entries {
+                    ser.write_byte_string(&key)?;
+                    ser.writer()?.write_all(&value)?;
+                }
+                ser
+            }
+        };
+        ser.writer()?.write_all(closing)?;
         Ok(())
     }
 }
@@ -369,44 +685,41 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
 // `serialize_entry` method allows serializers to optimize for the case where
 // key and value are both available simultaneously. In bencode it doesn't make a
 // difference so the default behavior for `serialize_entry` is fine.
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<W> ser::SerializeMap for Compound<'_, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
-    // The Serde data model allows map keys to be any serializable type. bencode
-    // only allows string keys so the implementation below will produce invalid
-    // bencode if the key serializes as something other than a string.
-    //
-    // A real bencode serializer would need to validate that map keys are strings.
-    // This can be done by using a different Serializer to serialize the key
-    // (instead of `&mut **self`) and having that other serializer only
-    // implement `serialize_str` and return an DeBencodingError on any other data type.
+    // The Serde data model allows map keys to be any serializable type.
+    // bencode only allows string keys, so keys are serialized by
+    // `MapKeySerializer`, which returns an error for any other data type.
     fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut **self)
+        Compound::serialize_key(self, key)
     }
 
-    // It doesn't make a difference whether the colon is printed at the end of
-    // `serialize_key` or at the beginning of `serialize_value`. In this case
-    // the code is a bit simpler having it here.
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        Compound::serialize_value(self, value)
     }
 
     fn end(self) -> Result<()> {
-        self.output += "e";
-        Ok(())
+        Compound::end(self, b"e")
     }
 }
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<W> ser::SerializeStruct for Compound<'_, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
@@ -414,19 +727,21 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut **self)?;
-        value.serialize(&mut **self)
+        self.serialize_key(key)?;
+        self.serialize_value(value)
     }
 
     fn end(self) -> Result<()> {
-        self.output += "e";
-        Ok(())
+        Compound::end(self, b"e")
     }
 }
 
 // Similar to `SerializeTupleVariant`, here the `end` method is responsible for
-// closing both of the curly braces opened by `serialize_struct_variant`.
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+// closing both of the dictionaries opened by `serialize_struct_variant`.
+impl<W> ser::SerializeStructVariant for Compound<'_, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = BencodingSerializeError;
 
@@ -434,12 +749,11 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut **self)?;
-        value.serialize(&mut **self)
+        self.serialize_key(key)?;
+        self.serialize_value(value)
     }
 
     fn end(self) -> Result<()> {
-        self.output += "ee";
-        Ok(())
+        Compound::end(self, b"ee")
     }
 }
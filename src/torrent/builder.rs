@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::hash::{self, is_valid_piece_length, PieceHasher, BLOCK_LEN};
+use super::{
+    FileEntry, FileTree, FileTreeNode, Info, Metainfo, TorrentError, V2File, MERKLE_HASH_LEN,
+};
+use crate::ser::to_canonical_writer;
+
+/// The smallest piece length allowed, which is the length of a v2 block
+pub const MIN_PIECE_LENGTH: u64 = BLOCK_LEN as u64;
+
+// The largest piece length that is chosen automatically
+const MAX_AUTO_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
+
+// Roughly how many pieces an automatically chosen piece length aims for
+const TARGET_PIECE_COUNT: u64 = 1500;
+
+/// Which versions of the protocol a built torrent supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TorrentVersion {
+    /// SHA-1 pieces, as described by BEP 3
+    #[default]
+    V1,
+    /// A file tree with SHA-256 merkle roots, as described by BEP 52
+    V2,
+    /// Both, with padding files so that every file starts at a piece boundary
+    Hybrid,
+}
+
+/// Makes a torrent from a file or directory, hashing its contents into pieces
+///
+/// ```no_run
+/// use bencoding::torrent::TorrentBuilder;
+///
+/// let torrent = TorrentBuilder::new("alice.txt")
+///     .announce("http://tracker.example.com:6969/announce")
+///     .comment("Alice's Adventures in Wonderland")
+///     .build()
+///     .unwrap();
+///
+/// let file = std::fs::File::create("alice.torrent").unwrap();
+/// bencoding::to_canonical_writer(file, &torrent).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TorrentBuilder {
+    path: PathBuf,
+    version: TorrentVersion,
+    piece_length: Option<u64>,
+    announce: Option<String>,
+    announce_list: Option<Vec<Vec<String>>>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    creation_date: Option<i64>,
+    private: bool,
+}
+
+impl TorrentBuilder {
+    /// Creates a builder for a torrent of the file or directory at the given
+    /// path. The creation date is set to now, and the torrent is marked as
+    /// created by this crate.
+    ///
+    /// Symbolic links inside a directory are skipped.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let creation_date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|since| i64::try_from(since.as_secs()).ok());
+
+        Self {
+            path: path.into(),
+            version: TorrentVersion::default(),
+            piece_length: None,
+            announce: None,
+            announce_list: None,
+            comment: None,
+            created_by: Some(concat!("bencoding/", env!("CARGO_PKG_VERSION")).to_string()),
+            creation_date,
+            private: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn version(mut self, version: TorrentVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the length of each piece, which must be a power of two of at
+    /// least 16 KiB. By default it's chosen from the total length of the
+    /// files.
+    #[must_use]
+    pub const fn piece_length(mut self, piece_length: u64) -> Self {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    #[must_use]
+    pub fn announce<S>(mut self, announce: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.announce = Some(announce.into());
+        self
+    }
+
+    /// Sets the tiers of trackers, as described by BEP 12
+    #[must_use]
+    pub fn announce_list(mut self, announce_list: Vec<Vec<String>>) -> Self {
+        self.announce_list = Some(announce_list);
+        self
+    }
+
+    #[must_use]
+    pub fn comment<S>(mut self, comment: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    #[must_use]
+    pub fn created_by(mut self, created_by: Option<String>) -> Self {
+        self.created_by = created_by;
+        self
+    }
+
+    /// Sets when the torrent was created, in seconds since the Unix epoch
+    #[must_use]
+    pub const fn creation_date(mut self, creation_date: Option<i64>) -> Self {
+        self.creation_date = creation_date;
+        self
+    }
+
+    /// Marks the torrent as private, as described by BEP 27
+    #[must_use]
+    pub const fn private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    /// Reads and hashes every file, returning the finished torrent
+    ///
+    /// # Errors
+    /// Fails if reading the files fails, if there are no files, if a file's
+    /// name isn't valid UTF-8, or if the piece length is invalid
+    pub fn build(&self) -> Result<Metainfo, TorrentError> {
+        let (name, files) = collect_files(&self.path)?;
+        let total_length: u64 = files.iter().map(|file| file.length).sum();
+
+        let piece_length = match self.piece_length {
+            Some(piece_length) if is_valid_piece_length(piece_length) => piece_length,
+            Some(piece_length) => return Err(TorrentError::InvalidPieceLength(piece_length)),
+            None => (total_length / TARGET_PIECE_COUNT)
+                .next_power_of_two()
+                .clamp(MIN_PIECE_LENGTH, MAX_AUTO_PIECE_LENGTH),
+        };
+        let piece_len = usize::try_from(piece_length)
+            .map_err(|_| TorrentError::InvalidPieceLength(piece_length))?;
+
+        let v1 = self.version != TorrentVersion::V2;
+        let v2 = self.version != TorrentVersion::V1;
+        let single_file = files.len() == 1 && files[0].components.is_empty();
+
+        let mut pieces = PieceHasher::new(piece_len);
+        let mut file_entries = Vec::new();
+        let mut tree = BTreeMap::new();
+        let mut piece_layers = BTreeMap::new();
+
+        for (index, file) in files.iter().enumerate() {
+            let (length, leaves) = hash_file(&file.path, v1.then_some(&mut pieces), v2)?;
+
+            let pieces_root = if v2 && length > 0 {
+                let (root, layer) = hash::merkle_tree(&leaves, piece_len);
+                if length > piece_length {
+                    piece_layers.insert(root, layer);
+                }
+                Some(root)
+            } else {
+                None
+            };
+
+            let mut path = file.components.clone();
+            if single_file {
+                path.push(name.clone());
+            }
+            if v2 {
+                insert_file(
+                    &mut tree,
+                    &path,
+                    V2File {
+                        length,
+                        pieces_root,
+                    },
+                );
+            }
+
+            file_entries.push(FileEntry {
+                length,
+                path,
+                md5sum: None,
+                attr: None,
+            });
+
+            // Every file in a hybrid torrent starts at a piece boundary, so
+            // that its pieces are the same as in the v2 torrent
+            let padding = (piece_length - length % piece_length) % piece_length;
+            if self.version == TorrentVersion::Hybrid && index + 1 < files.len() && padding != 0 {
+                pieces.pad_piece();
+                file_entries.push(FileEntry {
+                    length: padding,
+                    path: vec![".pad".to_string(), padding.to_string()],
+                    md5sum: None,
+                    attr: Some("p".to_string()),
+                });
+            }
+        }
+
+        let (length, files) = match (v1, single_file) {
+            (false, _) => (None, None),
+            (true, true) => (Some(file_entries[0].length), None),
+            (true, false) => (None, Some(file_entries)),
+        };
+
+        let info = Info {
+            name,
+            piece_length,
+            pieces: if v1 { Some(pieces.finish()) } else { None },
+            length,
+            md5sum: None,
+            files,
+            private: if self.private { Some(1) } else { None },
+            meta_version: if v2 { Some(2) } else { None },
+            file_tree: if v2 { Some(FileTree(tree)) } else { None },
+        };
+
+        let mut torrent = Metainfo::new(info)?;
+        torrent.announce.clone_from(&self.announce);
+        torrent.announce_list.clone_from(&self.announce_list);
+        torrent.comment.clone_from(&self.comment);
+        torrent.created_by.clone_from(&self.created_by);
+        torrent.creation_date = self.creation_date;
+        if v2 && !piece_layers.is_empty() {
+            torrent.piece_layers = Some(piece_layers);
+        }
+
+        Ok(torrent)
+    }
+
+    /// Builds the torrent and writes it canonically to the given sink, as a
+    /// `.torrent` file
+    ///
+    /// # Errors
+    /// Fails if building the torrent fails, or if writing to the sink fails
+    pub fn write<W>(&self, writer: W) -> Result<Metainfo, TorrentError>
+    where
+        W: io::Write,
+    {
+        let torrent = self.build()?;
+        to_canonical_writer(writer, &torrent)?;
+        Ok(torrent)
+    }
+}
+
+// Reads a file in blocks, feeding them to the v1 piece hasher if there is one,
+// and returning the file's length along with the v2 hashes of its blocks
+fn hash_file(
+    path: &Path,
+    mut pieces: Option<&mut PieceHasher>,
+    v2: bool,
+) -> io::Result<(u64, Vec<[u8; MERKLE_HASH_LEN]>)> {
+    let mut reader = File::open(path)?;
+    let mut buffer = vec![0; BLOCK_LEN];
+    let mut leaves = Vec::new();
+    let mut length = 0;
+
+    loop {
+        let read = hash::read_block(&mut reader, &mut buffer)?;
+        if read == 0 {
+            return Ok((length, leaves));
+        }
+
+        if let Some(pieces) = pieces.as_deref_mut() {
+            pieces.update(&buffer[..read]);
+        }
+        if v2 {
+            leaves.push(hash::sha256(&buffer[..read]));
+        }
+        length += read as u64;
+    }
+}
+
+// A file to be hashed, with its path inside the torrent's directory
+struct SourceFile {
+    path: PathBuf,
+    components: Vec<String>,
+    length: u64,
+}
+
+// Returns the torrent's name, and every file under the path sorted by path.
+// A single file has no components, as its name is the torrent's name.
+fn collect_files(path: &Path) -> Result<(String, Vec<SourceFile>), TorrentError> {
+    let name = path
+        .canonicalize()?
+        .file_name()
+        .map(|name| utf8_name(name, path))
+        .transpose()?
+        .ok_or(TorrentError::NoFiles)?;
+
+    let metadata = fs::metadata(path)?;
+    let mut files = Vec::new();
+
+    if metadata.is_dir() {
+        walk_directory(path, &mut Vec::new(), &mut files)?;
+    } else {
+        files.push(SourceFile {
+            path: path.to_path_buf(),
+            components: Vec::new(),
+            length: metadata.len(),
+        });
+    }
+
+    if files.is_empty() {
+        return Err(TorrentError::NoFiles);
+    }
+
+    Ok((name, files))
+}
+
+fn walk_directory(
+    directory: &Path,
+    components: &mut Vec<String>,
+    files: &mut Vec<SourceFile>,
+) -> Result<(), TorrentError> {
+    let mut entries = fs::read_dir(directory)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        let path = entry.path();
+        // Links aren't followed, as they can loop back on a directory or lead
+        // outside of the one the torrent is made from
+        let metadata = fs::symlink_metadata(&path)?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+
+        components.push(utf8_name(&entry.file_name(), &path)?);
+        if metadata.is_dir() {
+            walk_directory(&path, components, files)?;
+        } else {
+            files.push(SourceFile {
+                path,
+                components: components.clone(),
+                length: metadata.len(),
+            });
+        }
+
+        components.pop();
+    }
+
+    Ok(())
+}
+
+fn utf8_name(name: &std::ffi::OsStr, path: &Path) -> Result<String, TorrentError> {
+    name.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| TorrentError::InvalidPath(path.to_path_buf()))
+}
+
+// Adds a file to a v2 file tree, creating the directories on its path
+fn insert_file(directory: &mut BTreeMap<String, FileTreeNode>, path: &[String], file: V2File) {
+    match path {
+        [] => {}
+        [name] => {
+            directory.insert(name.clone(), FileTreeNode::File(file));
+        }
+        [name, rest @ ..] => {
+            let node = directory
+                .entry(name.clone())
+                .or_insert_with(|| FileTreeNode::Directory(BTreeMap::new()));
+            if let FileTreeNode::Directory(subdirectory) = node {
+                insert_file(subdirectory, rest, file);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::ser::BencodingSerializeError;
+
+#[derive(Error, Debug)]
+pub enum TorrentError {
+    #[error("an error occurred while reading or writing a file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("an error occurred while encoding the torrent: {0}")]
+    Serialize(#[from] BencodingSerializeError),
+    #[error("there are no files to make a torrent from")]
+    NoFiles,
+    #[error("piece length {0} is not a power of two of at least 16 KiB")]
+    InvalidPieceLength(u64),
+    #[error("path '{0}' has a name that isn't valid UTF-8")]
+    InvalidPath(PathBuf),
+    #[error("path '{0}' would be outside the download directory")]
+    UnsafePath(PathBuf),
+    #[error("the torrent is missing the piece layer of a file longer than a piece")]
+    MissingPieceLayer,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MagnetError {
+    #[error("a magnet link must start with 'magnet:?'")]
+    NotMagnet,
+    #[error("a magnet link must have a 'urn:btih:' or 'urn:btmh:' exact topic")]
+    MissingInfoHash,
+    #[error("'{0}' is not a valid info-hash")]
+    InvalidInfoHash(String),
+    #[error("'{0}' is not validly percent-encoded UTF-8")]
+    InvalidEncoding(String),
+    #[error("'{0}' is not a valid list of file indices")]
+    InvalidSelectOnly(String),
+}
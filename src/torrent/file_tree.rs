@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+
+/// The length of a SHA-256 merkle root or piece layer hash
+pub const MERKLE_HASH_LEN: usize = 32;
+
+/// The files of a v2 torrent, as described by BEP 52, mapping the name of each
+/// file or directory at the top level to its contents
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTree(pub BTreeMap<String, FileTreeNode>);
+
+impl FileTree {
+    /// Returns every file in the tree along with its path, sorted by path
+    #[must_use]
+    pub fn files(&self) -> Vec<(Vec<&str>, &V2File)> {
+        let mut files = Vec::new();
+        collect_files(&self.0, &mut Vec::new(), &mut files);
+        files
+    }
+}
+
+fn collect_files<'a>(
+    directory: &'a BTreeMap<String, FileTreeNode>,
+    path: &mut Vec<&'a str>,
+    files: &mut Vec<(Vec<&'a str>, &'a V2File)>,
+) {
+    for (name, node) in directory {
+        path.push(name);
+        match node {
+            FileTreeNode::File(file) => files.push((path.clone(), file)),
+            FileTreeNode::Directory(directory) => collect_files(directory, path, files),
+        }
+        path.pop();
+    }
+}
+
+/// A file or directory in a [`FileTree`]
+///
+/// A file is encoded as a dictionary whose only key is the empty string,
+/// mapping to the file's attributes, and a directory is a dictionary mapping
+/// the names of its contents to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileTreeNode {
+    File(V2File),
+    Directory(BTreeMap<String, Self>),
+}
+
+/// The attributes of a file in a [`FileTree`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct V2File {
+    pub length: u64,
+    /// The root of the merkle tree of the file's 16 KiB blocks, which every
+    /// file that isn't empty has
+    #[serde(
+        rename = "pieces root",
+        with = "serde_bytes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pieces_root: Option<[u8; MERKLE_HASH_LEN]>,
+}
+
+impl Serialize for FileTreeNode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::File(file) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("", file)?;
+                map.end()
+            }
+            Self::Directory(directory) => serializer.collect_map(directory),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FileTreeNode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(FileTreeNodeVisitor)
+    }
+}
+
+struct FileTreeNodeVisitor;
+
+impl<'de> Visitor<'de> for FileTreeNodeVisitor {
+    type Value = FileTreeNode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a file or directory")
+    }
+
+    // The empty key can't be the name of a file, so it marks this as a file
+    // rather than a directory
+    fn visit_map<A>(self, mut map: A) -> Result<FileTreeNode, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut directory = BTreeMap::new();
+        let mut file = None;
+
+        while let Some(name) = map.next_key::<String>()? {
+            if name.is_empty() {
+                file = Some(map.next_value()?);
+            } else {
+                directory.insert(name, map.next_value()?);
+            }
+        }
+
+        match file {
+            Some(file) if directory.is_empty() => Ok(FileTreeNode::File(file)),
+            Some(_) => Err(de::Error::custom(
+                "a file in the file tree can't also be a directory",
+            )),
+            None => Ok(FileTreeNode::Directory(directory)),
+        }
+    }
+}
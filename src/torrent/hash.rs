@@ -0,0 +1,145 @@
+use std::io::{self, Read};
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use super::{MERKLE_HASH_LEN, PIECE_HASH_LEN};
+
+/// The length of the blocks hashed into the leaves of a v2 file's merkle tree
+pub const BLOCK_LEN: usize = 16 * 1024;
+
+// Whether every piece is a whole number of blocks, as v2 requires
+pub const fn is_valid_piece_length(piece_length: u64) -> bool {
+    piece_length >= BLOCK_LEN as u64 && piece_length.is_power_of_two()
+}
+
+// Hashes a stream of bytes into v1 pieces, which can span several files
+pub struct PieceHasher {
+    piece_length: usize,
+    piece: Sha1,
+    // The number of bytes hashed into the current piece
+    filled: usize,
+    pieces: Vec<u8>,
+}
+
+impl PieceHasher {
+    pub fn new(piece_length: usize) -> Self {
+        Self {
+            piece_length,
+            piece: Sha1::new(),
+            filled: 0,
+            pieces: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let len = bytes.len().min(self.piece_length - self.filled);
+            self.piece.update(&bytes[..len]);
+            self.filled += len;
+            bytes = &bytes[len..];
+
+            if self.filled == self.piece_length {
+                self.finish_piece();
+            }
+        }
+    }
+
+    // Hashes zeros up to the end of the current piece, as a padding file does
+    pub fn pad_piece(&mut self) {
+        if self.filled != 0 {
+            let zeros = vec![0; self.piece_length - self.filled];
+            self.update(&zeros);
+        }
+    }
+
+    fn finish_piece(&mut self) {
+        let piece = std::mem::replace(&mut self.piece, Sha1::new());
+        self.pieces.extend_from_slice(&piece.finalize());
+        self.filled = 0;
+    }
+
+    // Returns the hashes of every piece, including the last partial piece
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.filled != 0 {
+            self.finish_piece();
+        }
+        self.pieces
+    }
+}
+
+pub fn sha1(bytes: &[u8]) -> [u8; PIECE_HASH_LEN] {
+    Sha1::digest(bytes).into()
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; MERKLE_HASH_LEN] {
+    Sha256::digest(bytes).into()
+}
+
+/// Builds the merkle tree of a v2 file from the hashes of its blocks,
+/// returning the root and the layer with one hash per piece. Leaves past the
+/// end of the file are zero, so the tree is a power of two wide.
+pub fn merkle_tree(
+    leaves: &[[u8; MERKLE_HASH_LEN]],
+    piece_length: usize,
+) -> ([u8; MERKLE_HASH_LEN], Vec<u8>) {
+    let blocks_per_piece = piece_length / BLOCK_LEN;
+    let piece_count = leaves.len().div_ceil(blocks_per_piece);
+
+    let mut layer = leaves.to_vec();
+    layer.resize(
+        leaves.len().max(1).next_power_of_two(),
+        [0; MERKLE_HASH_LEN],
+    );
+
+    let mut piece_layer = Vec::new();
+    let mut width = 1;
+    loop {
+        if width == blocks_per_piece {
+            piece_layer = layer[..piece_count].concat();
+        }
+
+        if layer.len() == 1 {
+            return (layer[0], piece_layer);
+        }
+
+        layer = layer
+            .chunks_exact(2)
+            .map(|pair| sha256(&[pair[0], pair[1]].concat()))
+            .collect();
+        width *= 2;
+    }
+}
+
+/// Returns the root of a merkle tree the given number of leaves wide, which
+/// must be a power of two. Leaves past the end of the slice are zero.
+pub fn merkle_root(leaves: &[[u8; MERKLE_HASH_LEN]], width: usize) -> [u8; MERKLE_HASH_LEN] {
+    let mut layer = leaves.to_vec();
+    layer.resize(width, [0; MERKLE_HASH_LEN]);
+
+    while layer.len() > 1 {
+        layer = layer
+            .chunks_exact(2)
+            .map(|pair| sha256(&[pair[0], pair[1]].concat()))
+            .collect();
+    }
+    layer[0]
+}
+
+// Fills as much of the buffer as the reader can, returning how many bytes
+// were read, which is only less than the buffer's length at the end of input
+pub fn read_block<R>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize>
+where
+    R: Read,
+{
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,272 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use super::{MagnetError, Metainfo, MERKLE_HASH_LEN, PIECE_HASH_LEN};
+use crate::percent;
+
+// The multihash prefix of a SHA-256 hash, its code and then its length
+const SHA256_MULTIHASH: [u8; 2] = [0x12, 0x20];
+
+/// A magnet link, as described by BEP 9, identifying a torrent by its
+/// info-hash so that its metainfo can be fetched from peers
+///
+/// ```
+/// use bencoding::torrent::MagnetLink;
+///
+/// let link: MagnetLink = "magnet:?xt=urn:btih:b9acdd69d7d8f90a3a082048155fc0c8458e62c7&dn=alice.txt"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(Some("alice.txt"), link.name.as_deref());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MagnetLink {
+    /// The v1 info-hash, from `xt=urn:btih:`, in hex or base32
+    pub info_hash_v1: Option<[u8; PIECE_HASH_LEN]>,
+    /// The v2 info-hash, from `xt=urn:btmh:` as a SHA-256 multihash, as
+    /// described by BEP 52
+    pub info_hash_v2: Option<[u8; MERKLE_HASH_LEN]>,
+    /// The name to show until the metainfo is fetched, from `dn`
+    pub name: Option<String>,
+    /// Tracker URLs, from `tr`
+    pub trackers: Vec<String>,
+    /// Web seed URLs, from `ws`, as described by BEP 19
+    pub web_seeds: Vec<String>,
+    /// The addresses of peers to connect to, from `x.pe`
+    pub peers: Vec<String>,
+    /// The indices of the only files to download, from `so`, as described by
+    /// BEP 53
+    pub select_only: Vec<RangeInclusive<usize>>,
+}
+
+impl MagnetLink {
+    /// Creates a magnet link with a v1 info-hash and nothing else
+    #[must_use]
+    pub fn from_info_hash_v1(info_hash: [u8; PIECE_HASH_LEN]) -> Self {
+        Self {
+            info_hash_v1: Some(info_hash),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a magnet link with a v2 info-hash and nothing else
+    #[must_use]
+    pub fn from_info_hash_v2(info_hash: [u8; MERKLE_HASH_LEN]) -> Self {
+        Self {
+            info_hash_v2: Some(info_hash),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&Metainfo> for MagnetLink {
+    /// Creates a magnet link with the torrent's info-hashes, name and trackers
+    fn from(torrent: &Metainfo) -> Self {
+        let mut trackers: Vec<String> = torrent.announce.iter().cloned().collect();
+        for tracker in torrent.announce_list.iter().flatten().flatten() {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+
+        Self {
+            info_hash_v1: if torrent.info().is_v1() {
+                Some(torrent.info_hash_v1())
+            } else {
+                None
+            },
+            info_hash_v2: torrent.info_hash_v2(),
+            name: Some(torrent.info().name.clone()),
+            trackers,
+            ..Self::default()
+        }
+    }
+}
+
+impl FromStr for MagnetLink {
+    type Err = MagnetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s.strip_prefix("magnet:?").ok_or(MagnetError::NotMagnet)?;
+        let mut link = Self::default();
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+
+            // Parameters may be numbered, such as `tr.1`, when there are
+            // several of them
+            let key = match key.rsplit_once('.') {
+                Some((base, index)) if index.bytes().all(|b| b.is_ascii_digit()) => base,
+                _ => key,
+            };
+
+            match key {
+                "xt" => link.parse_exact_topic(&value)?,
+                "dn" => link.name = Some(value),
+                "tr" => link.trackers.push(value),
+                "ws" => link.web_seeds.push(value),
+                "x.pe" => link.peers.push(value),
+                "so" => link.select_only = parse_select_only(&value)?,
+                _ => {}
+            }
+        }
+
+        if link.info_hash_v1.is_none() && link.info_hash_v2.is_none() {
+            return Err(MagnetError::MissingInfoHash);
+        }
+
+        Ok(link)
+    }
+}
+
+impl MagnetLink {
+    // Topics that aren't BitTorrent info-hashes are ignored
+    fn parse_exact_topic(&mut self, topic: &str) -> Result<(), MagnetError> {
+        let invalid = || MagnetError::InvalidInfoHash(topic.to_string());
+
+        if let Some(hash) = topic.strip_prefix("urn:btih:") {
+            let bytes = match hash.len() {
+                40 => decode_hex(hash),
+                32 => decode_base32(hash),
+                _ => None,
+            };
+            self.info_hash_v1 = Some(
+                bytes
+                    .and_then(|bytes| <[u8; PIECE_HASH_LEN]>::try_from(bytes).ok())
+                    .ok_or_else(invalid)?,
+            );
+        } else if let Some(hash) = topic.strip_prefix("urn:btmh:") {
+            let bytes = decode_hex(hash).ok_or_else(invalid)?;
+            self.info_hash_v2 = Some(
+                bytes
+                    .strip_prefix(&SHA256_MULTIHASH[..])
+                    .and_then(|hash| <[u8; MERKLE_HASH_LEN]>::try_from(hash).ok())
+                    .ok_or_else(invalid)?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("magnet:?")?;
+        let mut separator = "";
+        let mut param = |f: &mut fmt::Formatter<'_>, key: &str, value: &str| {
+            write!(f, "{separator}{key}={value}")?;
+            separator = "&";
+            Ok(())
+        };
+
+        if let Some(hash) = &self.info_hash_v1 {
+            param(f, "xt", &format!("urn:btih:{}", encode_hex(hash)))?;
+        }
+        if let Some(hash) = &self.info_hash_v2 {
+            let multihash = [&SHA256_MULTIHASH[..], hash].concat();
+            param(f, "xt", &format!("urn:btmh:{}", encode_hex(&multihash)))?;
+        }
+        if let Some(name) = &self.name {
+            param(f, "dn", &percent::encode(name.as_bytes()))?;
+        }
+        for tracker in &self.trackers {
+            param(f, "tr", &percent::encode(tracker.as_bytes()))?;
+        }
+        for web_seed in &self.web_seeds {
+            param(f, "ws", &percent::encode(web_seed.as_bytes()))?;
+        }
+        for peer in &self.peers {
+            param(f, "x.pe", &percent::encode(peer.as_bytes()))?;
+        }
+        if !self.select_only.is_empty() {
+            let ranges: Vec<String> = self
+                .select_only
+                .iter()
+                .map(|range| {
+                    if range.start() == range.end() {
+                        range.start().to_string()
+                    } else {
+                        format!("{}-{}", range.start(), range.end())
+                    }
+                })
+                .collect();
+            param(f, "so", &ranges.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Parses a list of file indices and inclusive ranges of them, such as
+// `0,2,4-6`
+fn parse_select_only(value: &str) -> Result<Vec<RangeInclusive<usize>>, MagnetError> {
+    let invalid = || MagnetError::InvalidSelectOnly(value.to_string());
+
+    value
+        .split(',')
+        .map(|item| {
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            let start = start.parse().map_err(|_| invalid())?;
+            let end = end.parse().map_err(|_| invalid())?;
+            if start > end {
+                return Err(invalid());
+            }
+            Ok(start..=end)
+        })
+        .collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` would also accept a sign, such as `+a`
+    if s.len() % 2 != 0 || !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, byte| {
+        let _ = write!(s, "{byte:02x}");
+        s
+    })
+}
+
+// Decodes RFC 4648 base32 without padding, in either case
+fn decode_base32(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer = 0_u64;
+    let mut bits = 0;
+
+    for c in s.bytes() {
+        let value = match c.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => c - b'A',
+            c @ b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u64::from(value);
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits).to_le_bytes()[0]);
+        }
+    }
+
+    Some(bytes)
+}
+
+fn percent_decode(s: &str) -> Result<String, MagnetError> {
+    percent::decode_query(s)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| MagnetError::InvalidEncoding(s.to_string()))
+}
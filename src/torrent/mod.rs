@@ -0,0 +1,392 @@
+//! Torrent metainfo files, as described by BEP 3, and BEP 52 for v2 and
+//! hybrid torrents.
+//!
+//! A [`Metainfo`] keeps the exact bytes its `info` dictionary was decoded
+//! from, so that its info-hash matches the one every other client computes,
+//! even if the dictionary wasn't encoded canonically or has keys this crate
+//! doesn't know about.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use serde::{de::Error as _, Deserialize, Serialize};
+
+use crate::de::BencodingDeserializeError;
+use crate::ser::BencodingSerializeError;
+use crate::RawBencode;
+
+mod builder;
+mod err;
+mod file_tree;
+mod hash;
+mod magnet;
+mod verify;
+
+pub use builder::{TorrentBuilder, TorrentVersion, MIN_PIECE_LENGTH};
+pub use err::{MagnetError, TorrentError};
+pub use file_tree::{FileTree, FileTreeNode, V2File, MERKLE_HASH_LEN};
+pub use magnet::MagnetLink;
+pub use verify::{Status, Verification};
+
+/// The length of a SHA-1 piece hash in `pieces`
+pub const PIECE_HASH_LEN: usize = 20;
+
+/// A decoded `.torrent` file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MetainfoFields", into = "MetainfoFields")]
+pub struct Metainfo {
+    /// The URL of the tracker
+    pub announce: Option<String>,
+    /// Tiers of tracker URLs, as described by BEP 12
+    pub announce_list: Option<Vec<Vec<String>>>,
+    pub comment: Option<String>,
+    /// The program that created the torrent
+    pub created_by: Option<String>,
+    /// When the torrent was created, in seconds since the Unix epoch
+    pub creation_date: Option<i64>,
+    /// The string encoding used for `info`'s `pieces`
+    pub encoding: Option<String>,
+    /// The layers of each v2 file's merkle tree that hold one hash per piece,
+    /// keyed by the file's `pieces root`. Files no longer than a piece have
+    /// no layer, as their root is the hash of the only piece.
+    pub piece_layers: Option<BTreeMap<[u8; MERKLE_HASH_LEN], Vec<u8>>>,
+    info: Info,
+    info_bytes: RawBencode,
+}
+
+impl Metainfo {
+    /// Creates a torrent with the given `info` dictionary, which is encoded
+    /// canonically
+    ///
+    /// # Errors
+    /// Fails if the `info` dictionary can't be serialized
+    pub fn new(info: Info) -> Result<Self, BencodingSerializeError> {
+        Ok(Self {
+            announce: None,
+            announce_list: None,
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            encoding: None,
+            piece_layers: None,
+            info_bytes: RawBencode::from_value(&info)?,
+            info,
+        })
+    }
+
+    /// Creates a torrent from the exact bytes of its `info` dictionary, such
+    /// as metadata fetched from peers, which are kept as they are so that
+    /// the info-hashes match
+    ///
+    /// # Errors
+    /// Fails if the bytes aren't a valid `info` dictionary
+    pub fn from_info_bytes(info_bytes: RawBencode) -> Result<Self, BencodingDeserializeError> {
+        let info: Info = info_bytes.decode()?;
+        info.validate()?;
+
+        Ok(Self {
+            announce: None,
+            announce_list: None,
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            encoding: None,
+            piece_layers: None,
+            info,
+            info_bytes,
+        })
+    }
+
+    #[must_use]
+    pub const fn info(&self) -> &Info {
+        &self.info
+    }
+
+    /// Returns the exact bytes of the `info` dictionary
+    #[must_use]
+    pub fn info_bytes(&self) -> &[u8] {
+        self.info_bytes.as_bytes()
+    }
+
+    /// Returns the SHA-1 hash of the `info` dictionary, which identifies the
+    /// torrent
+    #[must_use]
+    pub fn info_hash_v1(&self) -> [u8; 20] {
+        hash::sha1(self.info_bytes())
+    }
+
+    /// Returns the SHA-256 hash of the `info` dictionary, which identifies a
+    /// v2 or hybrid torrent, or `None` for a torrent that is only v1
+    #[must_use]
+    pub fn info_hash_v2(&self) -> Option<[u8; 32]> {
+        if self.info.is_v2() {
+            Some(hash::sha256(self.info_bytes()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the piece layer of the v2 file with the given `pieces root`
+    #[must_use]
+    pub fn piece_layer(&self, pieces_root: &[u8; MERKLE_HASH_LEN]) -> Option<&[u8]> {
+        self.piece_layers
+            .as_ref()
+            .and_then(|layers| layers.get(pieces_root))
+            .map(Vec::as_slice)
+    }
+}
+
+// The fields of a metainfo file as they are encoded, with `info` kept raw
+#[derive(Serialize, Deserialize)]
+struct MetainfoFields {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    announce: Option<String>,
+    #[serde(rename = "announce-list", skip_serializing_if = "Option::is_none")]
+    announce_list: Option<Vec<Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(rename = "created by", skip_serializing_if = "Option::is_none")]
+    created_by: Option<String>,
+    #[serde(rename = "creation date", skip_serializing_if = "Option::is_none")]
+    creation_date: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    info: RawBencode,
+    #[serde(
+        rename = "piece layers",
+        with = "piece_layers",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    piece_layers: Option<BTreeMap<[u8; MERKLE_HASH_LEN], Vec<u8>>>,
+}
+
+// Piece layers are keyed by raw hashes, and both the keys and the layers are
+// byte strings
+mod piece_layers {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_bytes::{ByteArray, ByteBuf, Bytes};
+
+    use super::MERKLE_HASH_LEN;
+
+    type PieceLayers = BTreeMap<[u8; MERKLE_HASH_LEN], Vec<u8>>;
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S>(layers: &Option<PieceLayers>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match layers {
+            Some(layers) => serializer.collect_map(
+                layers
+                    .iter()
+                    .map(|(root, layer)| (Bytes::new(root), Bytes::new(layer))),
+            ),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<PieceLayers>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let layers = BTreeMap::<ByteArray<MERKLE_HASH_LEN>, ByteBuf>::deserialize(deserializer)?;
+
+        Ok(Some(
+            layers
+                .into_iter()
+                .map(|(root, layer)| (root.into_array(), layer.into_vec()))
+                .collect(),
+        ))
+    }
+}
+
+impl TryFrom<MetainfoFields> for Metainfo {
+    type Error = BencodingDeserializeError;
+
+    fn try_from(fields: MetainfoFields) -> Result<Self, Self::Error> {
+        Ok(Self {
+            announce: fields.announce,
+            announce_list: fields.announce_list,
+            comment: fields.comment,
+            created_by: fields.created_by,
+            creation_date: fields.creation_date,
+            encoding: fields.encoding,
+            piece_layers: fields.piece_layers,
+            ..Self::from_info_bytes(fields.info)?
+        })
+    }
+}
+
+impl From<Metainfo> for MetainfoFields {
+    fn from(metainfo: Metainfo) -> Self {
+        Self {
+            announce: metainfo.announce,
+            announce_list: metainfo.announce_list,
+            comment: metainfo.comment,
+            created_by: metainfo.created_by,
+            creation_date: metainfo.creation_date,
+            encoding: metainfo.encoding,
+            info: metainfo.info_bytes,
+            piece_layers: metainfo.piece_layers,
+        }
+    }
+}
+
+/// The `info` dictionary of a torrent, describing the files it contains
+///
+/// A v1 torrent has `pieces`. If it's a single-file torrent it has a `length`,
+/// and `name` is the name of the file. A multi-file torrent has `files`
+/// instead, and `name` is the name of the directory they are in.
+///
+/// A v2 torrent has a `meta version` of 2 and a `file tree` instead, and a
+/// hybrid torrent has both, describing the same files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Info {
+    pub name: String,
+    /// The number of bytes in each piece, apart from the last which may be
+    /// shorter
+    #[serde(rename = "piece length")]
+    pub piece_length: u64,
+    /// The SHA-1 hashes of every v1 piece, one after the other
+    #[serde(with = "serde_bytes", default, skip_serializing_if = "Option::is_none")]
+    pub pieces: Option<Vec<u8>>,
+    /// The length of the file in a single-file torrent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5sum: Option<String>,
+    /// The files in a multi-file torrent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<FileEntry>>,
+    /// Whether peers should only be found through the torrent's trackers, as
+    /// described by BEP 27
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<i64>,
+    #[serde(rename = "meta version", skip_serializing_if = "Option::is_none")]
+    pub meta_version: Option<i64>,
+    /// The files in a v2 torrent
+    #[serde(rename = "file tree", skip_serializing_if = "Option::is_none")]
+    pub file_tree: Option<FileTree>,
+}
+
+impl Info {
+    /// Returns whether the torrent has v1 pieces
+    #[must_use]
+    pub const fn is_v1(&self) -> bool {
+        self.pieces.is_some()
+    }
+
+    /// Returns whether the torrent has a v2 file tree
+    #[must_use]
+    pub fn is_v2(&self) -> bool {
+        self.meta_version == Some(2)
+    }
+
+    #[must_use]
+    pub fn is_hybrid(&self) -> bool {
+        self.is_v1() && self.is_v2()
+    }
+
+    #[must_use]
+    pub const fn is_multi_file(&self) -> bool {
+        self.files.is_some()
+    }
+
+    #[must_use]
+    pub fn is_private(&self) -> bool {
+        self.private == Some(1)
+    }
+
+    /// Returns the combined length of every file in the torrent, including
+    /// any v1 padding files
+    #[must_use]
+    pub fn total_length(&self) -> u64 {
+        match (&self.files, self.length, &self.file_tree) {
+            (Some(files), _, _) => files.iter().map(|file| file.length).sum(),
+            (None, Some(length), _) => length,
+            (None, None, Some(tree)) => tree.files().iter().map(|(_, file)| file.length).sum(),
+            (None, None, None) => 0,
+        }
+    }
+
+    /// Returns the number of v1 pieces
+    #[must_use]
+    pub fn piece_count(&self) -> usize {
+        self.pieces
+            .as_ref()
+            .map_or(0, |pieces| pieces.len() / PIECE_HASH_LEN)
+    }
+
+    /// Returns the SHA-1 hash of every v1 piece, in order
+    pub fn piece_hashes(&self) -> impl Iterator<Item = &[u8]> {
+        self.pieces
+            .as_deref()
+            .unwrap_or_default()
+            .chunks_exact(PIECE_HASH_LEN)
+    }
+
+    // Checks the rules that the types alone can't enforce
+    fn validate(&self) -> Result<(), BencodingDeserializeError> {
+        if let Some(pieces) = &self.pieces {
+            if self.length.is_some() == self.files.is_some() {
+                return Err(BencodingDeserializeError::custom(
+                    "info must have exactly one of 'length' and 'files'",
+                ));
+            }
+
+            if pieces.len() % PIECE_HASH_LEN != 0 {
+                return Err(BencodingDeserializeError::custom(
+                    "'pieces' is not a whole number of SHA-1 hashes",
+                ));
+            }
+        }
+
+        match self.meta_version {
+            Some(2) if self.file_tree.is_none() => {
+                return Err(BencodingDeserializeError::custom(
+                    "a v2 info must have a 'file tree'",
+                ))
+            }
+            Some(2) => {}
+            Some(version) => {
+                return Err(BencodingDeserializeError::custom(format!(
+                    "unsupported meta version {version}"
+                )))
+            }
+            None if !self.is_v1() => {
+                return Err(BencodingDeserializeError::custom(
+                    "info must have either 'pieces' or a 'meta version'",
+                ))
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// A file in a multi-file torrent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub length: u64,
+    /// The file's path relative to the torrent's directory, with the file's
+    /// name last
+    pub path: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5sum: Option<String>,
+    /// Attributes of the file, as described by BEP 47, such as `p` for the
+    /// padding files that align files to pieces in a hybrid torrent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attr: Option<String>,
+}
+
+impl FileEntry {
+    /// Returns whether this is a padding file, which is never written to disk
+    #[must_use]
+    pub fn is_padding(&self) -> bool {
+        self.attr.as_ref().is_some_and(|attr| attr.contains('p'))
+    }
+}
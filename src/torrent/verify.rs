@@ -0,0 +1,390 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read};
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use sha1::{Digest, Sha1};
+
+use super::hash::{self, is_valid_piece_length, BLOCK_LEN};
+use super::{Info, Metainfo, TorrentError, V2File, MERKLE_HASH_LEN, PIECE_HASH_LEN};
+
+/// Whether a piece or file on disk matches the torrent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The data matches its hash
+    Good,
+    /// The data is all there, but doesn't match its hash
+    Bad,
+    /// Some of the data isn't on disk, because a file is missing or too short
+    Missing,
+}
+
+/// The status of every piece of a torrent's data on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pieces: Vec<Status>,
+}
+
+impl Verification {
+    /// Returns the status of each piece, in order
+    #[must_use]
+    pub fn pieces(&self) -> &[Status] {
+        &self.pieces
+    }
+
+    /// Returns the number of pieces with the given status
+    #[must_use]
+    pub fn count(&self, status: Status) -> usize {
+        self.pieces.iter().filter(|&&piece| piece == status).count()
+    }
+
+    /// Returns whether every piece is good
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.pieces.iter().all(|&piece| piece == Status::Good)
+    }
+
+    /// Returns the good pieces as a bitfield, as sent in a `bitfield` message.
+    /// The high bit of the first byte is the first piece, and any spare bits
+    /// at the end are zero.
+    #[must_use]
+    pub fn bitfield(&self) -> Vec<u8> {
+        let mut bitfield = vec![0; self.pieces.len().div_ceil(8)];
+        for (index, &piece) in self.pieces.iter().enumerate() {
+            if piece == Status::Good {
+                bitfield[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        bitfield
+    }
+}
+
+impl Metainfo {
+    /// Re-hashes every piece of the torrent's data in the given download
+    /// directory, which holds the file of a single-file torrent or the
+    /// directory of a multi-file one
+    ///
+    /// The v1 pieces are checked if the torrent has them, spanning file
+    /// boundaries. Otherwise the v2 pieces of each file are checked against
+    /// its piece layer.
+    ///
+    /// # Errors
+    /// Fails if reading a file fails for any reason other than it not
+    /// existing, if a path in the torrent would be outside the directory, or
+    /// if the torrent is missing something needed to check its pieces
+    pub fn verify<P>(&self, directory: P) -> Result<Verification, TorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let pieces = if self.info().is_v1() {
+            verify_v1(self.info(), directory.as_ref())?
+        } else {
+            self.verify_v2(directory.as_ref())?
+        };
+
+        Ok(Verification { pieces })
+    }
+
+    /// Checks every file of a v2 torrent against its merkle root, returning
+    /// each file's path in the file tree along with its status, sorted by
+    /// path. A torrent that is only v1 has no files to check.
+    ///
+    /// # Errors
+    /// Fails if reading a file fails for any reason other than it not
+    /// existing, or if a path in the torrent would be outside the directory
+    pub fn verify_files<P>(&self, directory: P) -> Result<Vec<(Vec<String>, Status)>, TorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let info = self.info();
+        if !info.is_v2() {
+            return Ok(Vec::new());
+        }
+        let piece_length = v2_piece_length(info)?;
+
+        v2_files(info, directory.as_ref())?
+            .into_iter()
+            .map(|(components, path, file)| {
+                let status = match file.pieces_root {
+                    _ if file.length == 0 && path.exists() => Status::Good,
+                    _ if file.length == 0 => Status::Missing,
+                    Some(root) => {
+                        let (read, leaves) = read_leaves(&path, file.length)?;
+                        if read < file.length {
+                            Status::Missing
+                        } else if hash::merkle_tree(&leaves, piece_length).0 == root {
+                            Status::Good
+                        } else {
+                            Status::Bad
+                        }
+                    }
+                    None => Status::Bad,
+                };
+                Ok((components, status))
+            })
+            .collect()
+    }
+
+    // Checks each file's v2 pieces, which never span files. A file no longer
+    // than a piece is a single piece, whose hash is the file's root.
+    fn verify_v2(&self, directory: &Path) -> Result<Vec<Status>, TorrentError> {
+        let info = self.info();
+        let piece_length = v2_piece_length(info)?;
+        let blocks_per_piece = piece_length / BLOCK_LEN;
+        let mut statuses = Vec::new();
+
+        for (_, path, file) in v2_files(info, directory)? {
+            let piece_count = usize::try_from(file.length.div_ceil(info.piece_length))
+                .map_err(|_| TorrentError::InvalidPieceLength(info.piece_length))?;
+            let root = match file.pieces_root {
+                Some(root) if piece_count > 0 => root,
+                Some(_) => continue,
+                None => {
+                    statuses.extend(iter::repeat(Status::Bad).take(piece_count));
+                    continue;
+                }
+            };
+
+            let (read, leaves) = read_leaves(&path, file.length)?;
+            if piece_count == 1 {
+                statuses.push(if read < file.length {
+                    Status::Missing
+                } else if hash::merkle_tree(&leaves, piece_length).0 == root {
+                    Status::Good
+                } else {
+                    Status::Bad
+                });
+                continue;
+            }
+
+            let layer = self
+                .piece_layer(&root)
+                .filter(|layer| layer.len() == piece_count * MERKLE_HASH_LEN)
+                .ok_or(TorrentError::MissingPieceLayer)?;
+
+            for (index, expected) in layer.chunks_exact(MERKLE_HASH_LEN).enumerate() {
+                let end = ((index as u64 + 1) * info.piece_length).min(file.length);
+                statuses.push(if read < end {
+                    Status::Missing
+                } else {
+                    let start = index * blocks_per_piece;
+                    let blocks = &leaves[start..leaves.len().min(start + blocks_per_piece)];
+                    if hash::merkle_root(blocks, blocks_per_piece) == expected {
+                        Status::Good
+                    } else {
+                        Status::Bad
+                    }
+                });
+            }
+        }
+
+        Ok(statuses)
+    }
+}
+
+fn verify_v1(info: &Info, directory: &Path) -> Result<Vec<Status>, TorrentError> {
+    let piece_length = usize::try_from(info.piece_length)
+        .ok()
+        .filter(|&piece_length| piece_length > 0)
+        .ok_or(TorrentError::InvalidPieceLength(info.piece_length))?;
+    let mut checker = PieceChecker::new(piece_length, info.pieces.as_deref().unwrap_or_default());
+
+    for (path, length) in v1_files(info, directory)? {
+        if let Some(path) = path {
+            let read = read_file(&path, length, |block| checker.update(block))?;
+            checker.skip(length - read);
+        } else {
+            // Padding files aren't on disk, and are all zeros
+            let zeros = [0; BLOCK_LEN];
+            let mut remaining = length;
+            while remaining > 0 {
+                let len = usize::try_from(remaining).map_or(BLOCK_LEN, |r| r.min(BLOCK_LEN));
+                checker.update(&zeros[..len]);
+                remaining -= len as u64;
+            }
+        }
+    }
+
+    Ok(checker.finish(info.piece_count()))
+}
+
+// Hashes a stream of bytes into v1 pieces, comparing each with its expected
+// hash. Bytes that aren't on disk are skipped, making their pieces missing.
+struct PieceChecker<'a> {
+    piece_length: usize,
+    hashes: &'a [u8],
+    piece: Sha1,
+    filled: usize,
+    missing: bool,
+    statuses: Vec<Status>,
+}
+
+impl<'a> PieceChecker<'a> {
+    fn new(piece_length: usize, hashes: &'a [u8]) -> Self {
+        Self {
+            piece_length,
+            hashes,
+            piece: Sha1::new(),
+            filled: 0,
+            missing: false,
+            statuses: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let len = bytes.len().min(self.piece_length - self.filled);
+            self.piece.update(&bytes[..len]);
+            self.filled += len;
+            bytes = &bytes[len..];
+
+            if self.filled == self.piece_length {
+                self.finish_piece();
+            }
+        }
+    }
+
+    fn skip(&mut self, mut len: u64) {
+        while len > 0 {
+            let remaining = self.piece_length - self.filled;
+            let skipped = usize::try_from(len).map_or(remaining, |len| len.min(remaining));
+            self.missing = true;
+            self.filled += skipped;
+            len -= skipped as u64;
+
+            if self.filled == self.piece_length {
+                self.finish_piece();
+            }
+        }
+    }
+
+    fn finish_piece(&mut self) {
+        let piece = std::mem::replace(&mut self.piece, Sha1::new()).finalize();
+        let start = self.statuses.len() * PIECE_HASH_LEN;
+        let expected = self.hashes.get(start..start + PIECE_HASH_LEN);
+
+        self.statuses.push(if self.missing {
+            Status::Missing
+        } else if expected == Some(&piece[..]) {
+            Status::Good
+        } else {
+            Status::Bad
+        });
+        self.filled = 0;
+        self.missing = false;
+    }
+
+    // Returns the status of every piece, where any pieces past the end of the
+    // files are missing
+    fn finish(mut self, piece_count: usize) -> Vec<Status> {
+        if self.filled != 0 {
+            self.finish_piece();
+        }
+        self.statuses.resize(piece_count, Status::Missing);
+        self.statuses
+    }
+}
+
+// Reads up to `length` bytes of a file in blocks, returning how many bytes
+// there were. A file that doesn't exist has none.
+fn read_file<F>(path: &Path, length: u64, mut f: F) -> io::Result<u64>
+where
+    F: FnMut(&[u8]),
+{
+    let mut reader = match File::open(path) {
+        Ok(file) => file.take(length),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut buffer = vec![0; BLOCK_LEN];
+    let mut read = 0;
+    loop {
+        let len = hash::read_block(&mut reader, &mut buffer)?;
+        if len == 0 {
+            return Ok(read);
+        }
+        f(&buffer[..len]);
+        read += len as u64;
+    }
+}
+
+// Reads a file, returning how many bytes there were and the hash of each block
+fn read_leaves(path: &Path, length: u64) -> io::Result<(u64, Vec<[u8; MERKLE_HASH_LEN]>)> {
+    let mut leaves = Vec::new();
+    let read = read_file(path, length, |block| leaves.push(hash::sha256(block)))?;
+    Ok((read, leaves))
+}
+
+fn v2_piece_length(info: &Info) -> Result<usize, TorrentError> {
+    usize::try_from(info.piece_length)
+        .ok()
+        .filter(|_| is_valid_piece_length(info.piece_length))
+        .ok_or(TorrentError::InvalidPieceLength(info.piece_length))
+}
+
+// Returns where each v1 file is on disk along with its length, or `None` for
+// padding files
+fn v1_files(info: &Info, directory: &Path) -> Result<Vec<(Option<PathBuf>, u64)>, TorrentError> {
+    match &info.files {
+        None => Ok(vec![(
+            Some(join(directory, iter::once(&info.name))?),
+            info.length.unwrap_or_default(),
+        )]),
+        Some(files) => files
+            .iter()
+            .map(|file| {
+                if file.is_padding() {
+                    Ok((None, file.length))
+                } else {
+                    let path = join(directory, iter::once(&info.name).chain(&file.path))?;
+                    Ok((Some(path), file.length))
+                }
+            })
+            .collect(),
+    }
+}
+
+type V2Files<'a> = Vec<(Vec<String>, PathBuf, &'a V2File)>;
+
+// Returns each v2 file's path in the file tree, where it is on disk, and its
+// attributes. A tree with a single file at the top level is a single-file
+// torrent, so that file isn't in the torrent's directory.
+fn v2_files<'a>(info: &'a Info, directory: &Path) -> Result<V2Files<'a>, TorrentError> {
+    let files = info
+        .file_tree
+        .as_ref()
+        .map(super::FileTree::files)
+        .unwrap_or_default();
+    let single_file = files.len() == 1 && files[0].0.len() == 1;
+
+    files
+        .into_iter()
+        .map(|(components, file)| {
+            let components: Vec<String> = components.into_iter().map(str::to_string).collect();
+            let path = if single_file {
+                join(directory, &components)?
+            } else {
+                join(directory, iter::once(&info.name).chain(&components))?
+            };
+            Ok((components, path, file))
+        })
+        .collect()
+}
+
+// Joins the names from a torrent onto the download directory, refusing any
+// that would lead outside it
+fn join<'a, I>(directory: &Path, names: I) -> Result<PathBuf, TorrentError>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut path = directory.to_path_buf();
+    for name in names {
+        path.push(name);
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(TorrentError::UnsafePath(path));
+        }
+    }
+    Ok(path)
+}
@@ -0,0 +1,83 @@
+//! The compact peer format of BEP 23 and BEP 7, where each peer is its IP
+//! address in network byte order followed by its port.
+
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// The length of a compact IPv4 peer
+pub const V4_LEN: usize = 6;
+
+/// The length of a compact IPv6 peer
+pub const V6_LEN: usize = 18;
+
+/// Decodes compact IPv4 peers, or returns `None` if the bytes aren't a whole
+/// number of them
+#[must_use]
+pub fn decode_v4(bytes: &[u8]) -> Option<Vec<SocketAddr>> {
+    if bytes.len() % V4_LEN != 0 {
+        return None;
+    }
+
+    Some(
+        bytes
+            .chunks_exact(V4_LEN)
+            .map(|peer| {
+                let ip = <[u8; 4]>::try_from(&peer[..4]).unwrap_or_default();
+                SocketAddr::new(Ipv4Addr::from(ip).into(), port(&peer[4..]))
+            })
+            .collect(),
+    )
+}
+
+/// Decodes compact IPv6 peers, or returns `None` if the bytes aren't a whole
+/// number of them
+#[must_use]
+pub fn decode_v6(bytes: &[u8]) -> Option<Vec<SocketAddr>> {
+    if bytes.len() % V6_LEN != 0 {
+        return None;
+    }
+
+    Some(
+        bytes
+            .chunks_exact(V6_LEN)
+            .map(|peer| {
+                let ip = <[u8; 16]>::try_from(&peer[..16]).unwrap_or_default();
+                SocketAddr::new(Ipv6Addr::from(ip).into(), port(&peer[16..]))
+            })
+            .collect(),
+    )
+}
+
+/// Encodes the IPv4 peers as compact peers, skipping any IPv6 peers
+pub fn encode_v4<'a, I>(peers: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a SocketAddr>,
+{
+    let mut bytes = Vec::new();
+    for peer in peers {
+        if let SocketAddr::V4(peer) = peer {
+            bytes.extend_from_slice(&peer.ip().octets());
+            bytes.extend_from_slice(&peer.port().to_be_bytes());
+        }
+    }
+    bytes
+}
+
+/// Encodes the IPv6 peers as compact peers, skipping any IPv4 peers
+pub fn encode_v6<'a, I>(peers: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a SocketAddr>,
+{
+    let mut bytes = Vec::new();
+    for peer in peers {
+        if let SocketAddr::V6(peer) = peer {
+            bytes.extend_from_slice(&peer.ip().octets());
+            bytes.extend_from_slice(&peer.port().to_be_bytes());
+        }
+    }
+    bytes
+}
+
+fn port(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TrackerError {
+    #[error("the announce is missing the '{0}' parameter")]
+    MissingParameter(&'static str),
+    #[error("the '{0}' parameter has an invalid value")]
+    InvalidParameter(String),
+    #[error("the '{0}' parameter isn't validly percent-encoded")]
+    InvalidEncoding(String),
+}
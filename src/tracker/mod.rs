@@ -0,0 +1,327 @@
+//! Messages exchanged with HTTP trackers, as described by BEP 3 and BEP 48
+//! for scrapes: the query string of an announce, and the bencoded responses.
+//!
+//! Peers may be sent as a list of dictionaries, or in the compact format of
+//! BEP 23 and BEP 7. Compact peers are decoded into [`SocketAddr`]s, while a
+//! peer in a dictionary may also be given by a DNS name.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+pub mod compact;
+mod err;
+mod request;
+
+pub use err::TrackerError;
+pub use request::{AnnounceRequest, Event};
+
+/// The length of an info-hash sent to a tracker, which is the v1 info-hash
+/// or a v2 info-hash truncated to the same length
+pub const INFO_HASH_LEN: usize = 20;
+
+/// The length of a peer's id
+pub const PEER_ID_LEN: usize = 20;
+
+/// A tracker's response to an announce
+///
+/// A tracker that refuses the announce only sends a `failure reason`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnounceResponse {
+    #[serde(rename = "failure reason", skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    #[serde(rename = "warning message", skip_serializing_if = "Option::is_none")]
+    pub warning_message: Option<String>,
+    /// How many seconds to wait before announcing again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+    /// How many seconds to wait at least before announcing again
+    #[serde(rename = "min interval", skip_serializing_if = "Option::is_none")]
+    pub min_interval: Option<u64>,
+    /// An id to send back with later announces
+    #[serde(rename = "tracker id", skip_serializing_if = "Option::is_none")]
+    pub tracker_id: Option<String>,
+    /// The number of seeders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complete: Option<u64>,
+    /// The number of leechers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incomplete: Option<u64>,
+    /// Peers from `peers`, which are encoded compactly unless any of them
+    /// have an id, an IPv6 address or a DNS name
+    #[serde(with = "peers", default, skip_serializing_if = "Vec::is_empty")]
+    pub peers: Vec<Peer>,
+    /// IPv6 peers from the compact `peers6`
+    #[serde(with = "peers6", default, skip_serializing_if = "Vec::is_empty")]
+    pub peers6: Vec<SocketAddr>,
+}
+
+impl AnnounceResponse {
+    /// Returns the address of every peer with an IP address, from both
+    /// `peers` and `peers6`, leaving out peers given by a DNS name, which
+    /// have to be resolved first
+    pub fn addrs(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.peers
+            .iter()
+            .filter_map(|peer| peer.addr.socket_addr())
+            .chain(self.peers6.iter().copied())
+    }
+}
+
+/// A peer in an [`AnnounceResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "PeerFields", into = "PeerFields")]
+pub struct Peer {
+    /// The peer's id, which compact peers don't have
+    pub id: Option<[u8; PEER_ID_LEN]>,
+    pub addr: PeerAddr,
+}
+
+impl From<SocketAddr> for Peer {
+    fn from(addr: SocketAddr) -> Self {
+        Self {
+            id: None,
+            addr: PeerAddr::Ip(addr),
+        }
+    }
+}
+
+/// Where a [`Peer`] can be reached
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PeerAddr {
+    Ip(SocketAddr),
+    /// A DNS name and port, which BEP 3 allows in place of an IP address
+    Host(String, u16),
+}
+
+impl PeerAddr {
+    /// Returns the address, or `None` if it's a DNS name
+    #[must_use]
+    pub const fn socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Ip(addr) => Some(*addr),
+            Self::Host(..) => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn port(&self) -> u16 {
+        match self {
+            Self::Ip(addr) => addr.port(),
+            Self::Host(_, port) => *port,
+        }
+    }
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Ip(addr)
+    }
+}
+
+// A peer as it's encoded in a list of dictionaries
+#[derive(Serialize, Deserialize)]
+struct PeerFields {
+    #[serde(
+        rename = "peer id",
+        with = "serde_bytes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    peer_id: Option<[u8; PEER_ID_LEN]>,
+    ip: String,
+    port: u16,
+}
+
+impl TryFrom<PeerFields> for Peer {
+    type Error = String;
+
+    fn try_from(fields: PeerFields) -> Result<Self, Self::Error> {
+        let addr = match fields.ip.parse::<IpAddr>() {
+            Ok(ip) => PeerAddr::Ip(SocketAddr::new(ip, fields.port)),
+            Err(_) if fields.ip.is_empty() => return Err("a peer's 'ip' is empty".to_string()),
+            Err(_) => PeerAddr::Host(fields.ip, fields.port),
+        };
+
+        Ok(Self {
+            id: fields.peer_id,
+            addr,
+        })
+    }
+}
+
+impl From<Peer> for PeerFields {
+    fn from(peer: Peer) -> Self {
+        let port = peer.addr.port();
+        let ip = match peer.addr {
+            PeerAddr::Ip(addr) => addr.ip().to_string(),
+            PeerAddr::Host(host, _) => host,
+        };
+
+        Self {
+            peer_id: peer.id,
+            ip,
+            port,
+        }
+    }
+}
+
+// `peers` is either a list of dictionaries or a compact byte string
+mod peers {
+    use super::{compact, de, fmt, Deserializer, Peer, PeerAddr, SeqAccess, Serializer, Visitor};
+
+    pub fn serialize<S>(peers: &[Peer], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let compact: Option<Vec<_>> = peers
+            .iter()
+            .map(|peer| match peer.addr {
+                PeerAddr::Ip(addr) if peer.id.is_none() && addr.is_ipv4() => Some(addr),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(addrs) = compact {
+            serializer.serialize_bytes(&compact::encode_v4(&addrs))
+        } else {
+            serializer.collect_seq(peers)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Peer>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PeersVisitor)
+    }
+
+    struct PeersVisitor;
+
+    impl<'de> Visitor<'de> for PeersVisitor {
+        type Value = Vec<Peer>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of peers, or compact IPv4 peers")
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            compact::decode_v4(v)
+                .map(|addrs| addrs.into_iter().map(Peer::from).collect())
+                .ok_or_else(|| E::invalid_length(v.len(), &self))
+        }
+
+        // Compact peers are visited as a string when they happen to be valid
+        // UTF-8
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            self.visit_bytes(v.as_bytes())
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut peers = Vec::new();
+            while let Some(peer) = seq.next_element()? {
+                peers.push(peer);
+            }
+            Ok(peers)
+        }
+    }
+}
+
+// `peers6` is always compact
+mod peers6 {
+    use std::net::SocketAddr;
+
+    use serde::Deserialize;
+    use serde_bytes::ByteBuf;
+
+    use super::{compact, de, Deserializer, Serializer};
+
+    pub fn serialize<S>(peers: &[SocketAddr], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&compact::encode_v6(peers))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<SocketAddr>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        compact::decode_v6(&bytes).ok_or_else(|| {
+            de::Error::invalid_length(bytes.len(), &"a whole number of compact IPv6 peers")
+        })
+    }
+}
+
+/// A tracker's response to a scrape, with the statistics of each torrent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrapeResponse {
+    #[serde(rename = "failure reason", skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    /// The statistics of each torrent, keyed by its raw info-hash
+    #[serde(with = "scrape_files", default)]
+    pub files: BTreeMap<[u8; INFO_HASH_LEN], ScrapeStats>,
+}
+
+/// The statistics of a torrent in a [`ScrapeResponse`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrapeStats {
+    /// The number of seeders
+    pub complete: u64,
+    /// The number of times the torrent has been completely downloaded
+    pub downloaded: u64,
+    /// The number of leechers
+    pub incomplete: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+// Scrape files are keyed by raw info-hashes, which are byte strings
+mod scrape_files {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_bytes::{ByteArray, Bytes};
+
+    use super::{ScrapeStats, INFO_HASH_LEN};
+
+    type Files = BTreeMap<[u8; INFO_HASH_LEN], ScrapeStats>;
+
+    pub fn serialize<S>(files: &Files, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(
+            files
+                .iter()
+                .map(|(info_hash, stats)| (Bytes::new(info_hash), stats)),
+        )
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Files, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let files = BTreeMap::<ByteArray<INFO_HASH_LEN>, ScrapeStats>::deserialize(deserializer)?;
+
+        Ok(files
+            .into_iter()
+            .map(|(info_hash, stats)| (info_hash.into_array(), stats))
+            .collect())
+    }
+}
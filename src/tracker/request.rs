@@ -0,0 +1,212 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{TrackerError, INFO_HASH_LEN, PEER_ID_LEN};
+use crate::percent;
+
+/// Why a client is announcing, other than to ask for more peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Started,
+    Completed,
+    Stopped,
+}
+
+impl Event {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Completed => "completed",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+/// The query string a client sends to an HTTP tracker to announce itself
+///
+/// ```
+/// use bencoding::tracker::{AnnounceRequest, Event};
+///
+/// let mut request = AnnounceRequest::new([0xb9; 20], *b"-BE0001-123456789012", 6881);
+/// request.left = 36360;
+/// request.event = Some(Event::Started);
+///
+/// let url = request.url("http://tracker.example.com:6969/announce");
+/// let query = url.split_once('?').unwrap().1;
+///
+/// assert_eq!(request, query.parse().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnounceRequest {
+    pub info_hash: [u8; INFO_HASH_LEN],
+    pub peer_id: [u8; PEER_ID_LEN],
+    /// The port the client is listening on
+    pub port: u16,
+    pub uploaded: u64,
+    pub downloaded: u64,
+    /// The number of bytes the client still has to download
+    pub left: u64,
+    pub event: Option<Event>,
+    /// Whether the client accepts compact peers, as described by BEP 23
+    pub compact: Option<bool>,
+    /// The number of peers the client wants
+    pub numwant: Option<u32>,
+    /// A secret that identifies the client across IP address changes
+    pub key: Option<String>,
+    /// The `tracker id` from a previous response
+    pub tracker_id: Option<String>,
+}
+
+impl AnnounceRequest {
+    /// Creates a request for a client that hasn't transferred anything, with
+    /// no event, that accepts compact peers
+    #[must_use]
+    pub const fn new(
+        info_hash: [u8; INFO_HASH_LEN],
+        peer_id: [u8; PEER_ID_LEN],
+        port: u16,
+    ) -> Self {
+        Self {
+            info_hash,
+            peer_id,
+            port,
+            uploaded: 0,
+            downloaded: 0,
+            left: 0,
+            event: None,
+            compact: Some(true),
+            numwant: None,
+            key: None,
+            tracker_id: None,
+        }
+    }
+
+    /// Returns the percent-encoded query string, without a leading `?`
+    #[must_use]
+    pub fn to_query_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns the URL to announce to, adding the query string to the
+    /// tracker's announce URL, which may already have a query of its own
+    #[must_use]
+    pub fn url(&self, announce: &str) -> String {
+        let separator = if announce.contains('?') { '&' } else { '?' };
+        format!("{announce}{separator}{self}")
+    }
+}
+
+impl fmt::Display for AnnounceRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}",
+            percent::encode(&self.info_hash),
+            percent::encode(&self.peer_id),
+            self.port,
+            self.uploaded,
+            self.downloaded,
+            self.left
+        )?;
+
+        if let Some(event) = self.event {
+            write!(f, "&event={}", event.as_str())?;
+        }
+        if let Some(compact) = self.compact {
+            write!(f, "&compact={}", u8::from(compact))?;
+        }
+        if let Some(numwant) = self.numwant {
+            write!(f, "&numwant={numwant}")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "&key={}", percent::encode(key.as_bytes()))?;
+        }
+        if let Some(tracker_id) = &self.tracker_id {
+            write!(f, "&trackerid={}", percent::encode(tracker_id.as_bytes()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for AnnounceRequest {
+    type Err = TrackerError;
+
+    /// Parses a query string, as a tracker receives it, ignoring any
+    /// parameters it doesn't know
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s.strip_prefix('?').unwrap_or(s);
+
+        let mut info_hash = None;
+        let mut peer_id = None;
+        let mut port = None;
+        let mut uploaded = None;
+        let mut downloaded = None;
+        let mut left = None;
+        let mut request = Self::new([0; INFO_HASH_LEN], [0; PEER_ID_LEN], 0);
+        request.compact = None;
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            // A `+` is only a space in the parameters that are text
+            let decode = match key {
+                "info_hash" | "peer_id" => percent::decode,
+                _ => percent::decode_query,
+            };
+            let value =
+                decode(value).ok_or_else(|| TrackerError::InvalidEncoding(key.to_string()))?;
+
+            match key {
+                "info_hash" => info_hash = Some(parse_array(key, value)?),
+                "peer_id" => peer_id = Some(parse_array(key, value)?),
+                "port" => port = Some(parse_number(key, &value)?),
+                "uploaded" => uploaded = Some(parse_number(key, &value)?),
+                "downloaded" => downloaded = Some(parse_number(key, &value)?),
+                "left" => left = Some(parse_number(key, &value)?),
+                "event" => {
+                    request.event = match &value[..] {
+                        b"started" => Some(Event::Started),
+                        b"completed" => Some(Event::Completed),
+                        b"stopped" => Some(Event::Stopped),
+                        b"" | b"empty" => None,
+                        _ => return Err(TrackerError::InvalidParameter(key.to_string())),
+                    }
+                }
+                "compact" => request.compact = Some(parse_number::<u8>(key, &value)? != 0),
+                "numwant" => request.numwant = Some(parse_number(key, &value)?),
+                "key" => request.key = Some(parse_string(key, value)?),
+                "trackerid" => request.tracker_id = Some(parse_string(key, value)?),
+                _ => {}
+            }
+        }
+
+        request.info_hash = info_hash.ok_or(TrackerError::MissingParameter("info_hash"))?;
+        request.peer_id = peer_id.ok_or(TrackerError::MissingParameter("peer_id"))?;
+        request.port = port.ok_or(TrackerError::MissingParameter("port"))?;
+        request.uploaded = uploaded.ok_or(TrackerError::MissingParameter("uploaded"))?;
+        request.downloaded = downloaded.ok_or(TrackerError::MissingParameter("downloaded"))?;
+        request.left = left.ok_or(TrackerError::MissingParameter("left"))?;
+
+        Ok(request)
+    }
+}
+
+fn parse_array<const N: usize>(key: &str, value: Vec<u8>) -> Result<[u8; N], TrackerError> {
+    <[u8; N]>::try_from(value).map_err(|_| TrackerError::InvalidParameter(key.to_string()))
+}
+
+fn parse_number<T>(key: &str, value: &[u8]) -> Result<T, TrackerError>
+where
+    T: FromStr,
+{
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| TrackerError::InvalidParameter(key.to_string()))
+}
+
+fn parse_string(key: &str, value: Vec<u8>) -> Result<String, TrackerError> {
+    String::from_utf8(value).map_err(|_| TrackerError::InvalidParameter(key.to_string()))
+}
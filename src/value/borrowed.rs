@@ -0,0 +1,247 @@
+use std::collections::{btree_map, BTreeMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser;
+use serde::{Deserialize, Serialize};
+
+use super::{Index, RawBytes, Value};
+use crate::de::BencodingDeserializeError;
+
+/// A bencoded value whose byte strings borrow from the input they were
+/// decoded from, rather than being copied out of it
+/// ```
+/// use bencoding::{from_bytes, ValueRef};
+///
+/// let input = b"d8:intervali1800e5:peers6:\x7f\x00\x00\x01\x1a\xe1e";
+/// let value: ValueRef = from_bytes(input).unwrap();
+///
+/// assert_eq!(Some(1800), value["interval"].as_int());
+/// assert_eq!(Some(&input[26..32]), value["peers"].as_bytes());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ValueRef<'de> {
+    Int(i64),
+    Bytes(&'de [u8]),
+    List(Vec<Self>),
+    Dict(BTreeMap<&'de [u8], Self>),
+}
+
+impl<'de> ValueRef<'de> {
+    /// Looks up a value by dictionary key or list position, returning `None`
+    /// if the value is the wrong type or the key or position is missing
+    pub fn get<I: Index>(&self, index: I) -> Option<&Self> {
+        index.index_into_ref(self)
+    }
+
+    /// Copies every borrowed byte string, producing an owned [`Value`]
+    #[must_use]
+    pub fn into_owned(self) -> Value {
+        match self {
+            Self::Int(i) => Value::Int(i),
+            Self::Bytes(bytes) => Value::Bytes(bytes.to_vec()),
+            Self::List(list) => Value::List(list.into_iter().map(Self::into_owned).collect()),
+            Self::Dict(dict) => Value::Dict(
+                dict.into_iter()
+                    .map(|(k, v)| (k.to_vec(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_int(&self) -> bool {
+        matches!(self, Self::Int(_))
+    }
+
+    #[must_use]
+    pub const fn is_bytes(&self) -> bool {
+        matches!(self, Self::Bytes(_))
+    }
+
+    #[must_use]
+    pub const fn is_list(&self) -> bool {
+        matches!(self, Self::List(_))
+    }
+
+    #[must_use]
+    pub const fn is_dict(&self) -> bool {
+        matches!(self, Self::Dict(_))
+    }
+
+    #[must_use]
+    pub const fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte string with the lifetime of the input, rather than of
+    /// this value
+    #[must_use]
+    pub const fn as_bytes(&self) -> Option<&'de [u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte string as a `&str`, if it is one and is valid UTF-8
+    #[must_use]
+    pub fn as_str(&self) -> Option<&'de str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    #[must_use]
+    pub const fn as_list(&self) -> Option<&Vec<Self>> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_dict(&self) -> Option<&BTreeMap<&'de [u8], Self>> {
+        match self {
+            Self::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> From<ValueRef<'de>> for Value {
+    fn from(value: ValueRef<'de>) -> Self {
+        value.into_owned()
+    }
+}
+
+impl<I> ops::Index<I> for ValueRef<'_>
+where
+    I: Index,
+{
+    type Output = Self;
+
+    fn index(&self, index: I) -> &Self {
+        index
+            .index_into_ref(self)
+            .expect("no entry found for key or position")
+    }
+}
+
+impl Serialize for ValueRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            Self::Int(i) => serializer.serialize_i64(*i),
+            Self::Bytes(bytes) => serializer.serialize_bytes(bytes),
+            Self::List(list) => serializer.collect_seq(list),
+            Self::Dict(dict) => serializer.collect_map(dict.iter().map(|(k, v)| (RawBytes(k), v))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueRefVisitor)
+    }
+}
+
+struct ValueRefVisitor;
+
+impl<'de> Visitor<'de> for ValueRefVisitor {
+    type Value = ValueRef<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any bencoded value borrowed from the input")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<ValueRef<'de>, E> {
+        Ok(ValueRef::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<ValueRef<'de>, E>
+    where
+        E: de::Error,
+    {
+        i64::try_from(v)
+            .map(ValueRef::Int)
+            .map_err(|_| E::custom("integer out of range for a value"))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<ValueRef<'de>, E> {
+        Ok(ValueRef::Bytes(v.as_bytes()))
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<ValueRef<'de>, E> {
+        Ok(ValueRef::Bytes(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ValueRef<'de>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            list.push(element);
+        }
+        Ok(ValueRef::List(list))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<ValueRef<'de>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut dict = BTreeMap::new();
+        while let Some((BorrowedKey(key), value)) = map.next_entry()? {
+            match dict.entry(key) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    return Err(de::Error::custom(BencodingDeserializeError::DuplicateKey(
+                        String::from_utf8_lossy(entry.key()).into_owned(),
+                    )))
+                }
+            }
+        }
+        Ok(ValueRef::Dict(dict))
+    }
+}
+
+struct BorrowedKey<'de>(&'de [u8]);
+
+impl<'de> Deserialize<'de> for BorrowedKey<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(BorrowedKeyVisitor)
+    }
+}
+
+struct BorrowedKeyVisitor;
+
+impl<'de> Visitor<'de> for BorrowedKeyVisitor {
+    type Value = BorrowedKey<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string borrowed from the input")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<BorrowedKey<'de>, E> {
+        Ok(BorrowedKey(v.as_bytes()))
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<BorrowedKey<'de>, E> {
+        Ok(BorrowedKey(v))
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::{btree_map, BTreeMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::Deserialize;
+
+use super::Value;
+use crate::de::BencodingDeserializeError;
+
+type Result<T> = std::result::Result<T, BencodingDeserializeError>;
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any bencoded value")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| E::custom("integer out of range for a value"))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            list.push(element);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut dict = BTreeMap::new();
+        while let Some((DictKey(key), value)) = map.next_entry()? {
+            match dict.entry(key) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    return Err(de::Error::custom(BencodingDeserializeError::DuplicateKey(
+                        String::from_utf8_lossy(entry.key()).into_owned(),
+                    )))
+                }
+            }
+        }
+        Ok(Value::Dict(dict))
+    }
+}
+
+// Dictionary keys may be handed to us as either strings or raw bytes,
+// depending on whether they're valid UTF-8.
+struct DictKey(Vec<u8>);
+
+impl<'de> Deserialize<'de> for DictKey {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(DictKeyVisitor)
+    }
+}
+
+struct DictKeyVisitor;
+
+impl Visitor<'_> for DictKeyVisitor {
+    type Value = DictKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<DictKey, E> {
+        Ok(DictKey(v.as_bytes().to_vec()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<DictKey, E> {
+        Ok(DictKey(v.into_bytes()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<DictKey, E> {
+        Ok(DictKey(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<DictKey, E> {
+        Ok(DictKey(v))
+    }
+}
+
+// Visits a byte string as a `String` where possible, so that types expecting
+// strings can be deserialized from a `Value`, and as raw bytes otherwise.
+fn visit_byte_string<'de, V>(bytes: Vec<u8>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match String::from_utf8(bytes) {
+        Ok(s) => visitor.visit_string(s),
+        Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = BencodingDeserializeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Int(i) => visitor.visit_i64(i),
+            Self::Bytes(bytes) => visit_byte_string(bytes, visitor),
+            Self::List(list) => visitor.visit_seq(ListDeserializer {
+                iter: list.into_iter(),
+            }),
+            Self::Dict(dict) => visitor.visit_map(DictDeserializer {
+                iter: dict.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    // Bencode has no null, so a value that is present is always `Some`
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Enums are externally tagged, as either the name of a unit variant, or
+    // a dictionary with a single entry mapping the name to the data
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Bytes(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Self::Dict(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.into_iter().next().expect("dict has one entry");
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            other => Err(de::Error::invalid_type(
+                other.unexpected(),
+                &"a byte string or a dictionary with a single entry",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+impl Value {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Self::Int(i) => Unexpected::Signed(*i),
+            Self::Bytes(bytes) => Unexpected::Bytes(bytes),
+            Self::List(_) => Unexpected::Seq,
+            Self::Dict(_) => Unexpected::Map,
+        }
+    }
+}
+
+impl IntoDeserializer<'_, BencodingDeserializeError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct ListDeserializer {
+    iter: std::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for ListDeserializer {
+    type Error = BencodingDeserializeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.iter
+            .next()
+            .map(|value| seed.deserialize(value))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct DictDeserializer {
+    iter: btree_map::IntoIter<Vec<u8>, Value>,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for DictDeserializer {
+    type Error = BencodingDeserializeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Value::Bytes(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("next_value_seed called before next_key_seed"))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: Vec<u8>,
+    // The data of the variant, which unit variants don't have
+    value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = BencodingDeserializeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(Value::Bytes(self.variant))?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: Option<Value>,
+}
+
+impl VariantDeserializer {
+    fn into_value(self, expected: &str) -> Result<Value> {
+        self.value
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::UnitVariant, &expected))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = BencodingDeserializeError;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.into_value("newtype variant")?)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self.into_value("tuple variant")?, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self.into_value("struct variant")?, visitor)
+    }
+}
@@ -0,0 +1,127 @@
+use std::ops;
+
+use super::{Value, ValueRef};
+
+/// A type that can be used to index into a [`Value`] or [`ValueRef`]
+///
+/// Integers index into lists, and strings or byte strings index into
+/// dictionaries. This trait is sealed and can't be implemented outside of this
+/// crate.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
+
+    #[doc(hidden)]
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>>;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        v.as_list()?.get(*self)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        v.as_list_mut()?.get_mut(*self)
+    }
+
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>> {
+        v.as_list()?.get(*self)
+    }
+}
+
+impl Index for [u8] {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        v.as_dict()?.get(self)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        v.as_dict_mut()?.get_mut(self)
+    }
+
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>> {
+        v.as_dict()?.get(self)
+    }
+}
+
+impl Index for str {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self.as_bytes().index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_bytes().index_into_mut(v)
+    }
+
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>> {
+        self.as_bytes().index_into_ref(v)
+    }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self.as_str().index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_str().index_into_mut(v)
+    }
+
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>> {
+        self.as_str().index_into_ref(v)
+    }
+}
+
+impl<T> Index for &T
+where
+    T: ?Sized + Index,
+{
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(v)
+    }
+
+    fn index_into_ref<'v, 'de>(&self, v: &'v ValueRef<'de>) -> Option<&'v ValueRef<'de>> {
+        (**self).index_into_ref(v)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for [u8] {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T> Sealed for &T where T: ?Sized + Sealed {}
+}
+
+// Unlike `get`, indexing panics if the key or position is missing, the same
+// as indexing into a `Vec` or `BTreeMap` does.
+impl<I> ops::Index<I> for Value
+where
+    I: Index,
+{
+    type Output = Self;
+
+    fn index(&self, index: I) -> &Self {
+        index
+            .index_into(self)
+            .expect("no entry found for key or position")
+    }
+}
+
+impl<I> ops::IndexMut<I> for Value
+where
+    I: Index,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self {
+        index
+            .index_into_mut(self)
+            .expect("no entry found for key or position")
+    }
+}
@@ -0,0 +1,204 @@
+//! A dynamically typed representation of any bencoded value, for documents
+//! whose shape isn't known ahead of time.
+//! ```
+//! use bencoding::{from_bytes, Value};
+//!
+//! let value: Value = from_bytes(b"d4:spaml1:a1:bee").unwrap();
+//!
+//! assert_eq!(Some("a"), value["spam"][0].as_str());
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+mod borrowed;
+mod de;
+mod index;
+mod raw;
+mod ser;
+
+pub use borrowed::ValueRef;
+pub use index::Index;
+pub use raw::RawBencode;
+pub(crate) use raw::{raw_bytes, TOKEN as RAW_TOKEN};
+use ser::RawBytes;
+pub use ser::Serializer;
+
+use crate::config::Config;
+use crate::de::BencodingDeserializeError;
+use crate::ser::BencodingSerializeError;
+
+/// Any valid bencoded value
+///
+/// Dictionary keys are byte strings, kept sorted by their raw bytes as
+/// required by the specification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Self>),
+    Dict(BTreeMap<Vec<u8>, Self>),
+}
+
+impl Value {
+    /// Looks up a value by dictionary key or list position, returning `None`
+    /// if the value is the wrong type or the key or position is missing
+    pub fn get<I: Index>(&self, index: I) -> Option<&Self> {
+        index.index_into(self)
+    }
+
+    /// Mutable version of [`Value::get`]
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Self> {
+        index.index_into_mut(self)
+    }
+
+    #[must_use]
+    pub const fn is_int(&self) -> bool {
+        matches!(self, Self::Int(_))
+    }
+
+    #[must_use]
+    pub const fn is_bytes(&self) -> bool {
+        matches!(self, Self::Bytes(_))
+    }
+
+    #[must_use]
+    pub const fn is_list(&self) -> bool {
+        matches!(self, Self::List(_))
+    }
+
+    #[must_use]
+    pub const fn is_dict(&self) -> bool {
+        matches!(self, Self::Dict(_))
+    }
+
+    #[must_use]
+    pub const fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte string as a `&str`, if it is one and is valid UTF-8
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    #[must_use]
+    pub const fn as_list(&self) -> Option<&Vec<Self>> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub const fn as_list_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Self>> {
+        match self {
+            Self::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    pub const fn as_dict_mut(&mut self) -> Option<&mut BTreeMap<Vec<u8>, Self>> {
+        match self {
+            Self::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::Bytes(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::Bytes(s.into_bytes())
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(bytes: &[u8]) -> Self {
+        Self::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<Self>> for Value {
+    fn from(list: Vec<Self>) -> Self {
+        Self::List(list)
+    }
+}
+
+impl From<BTreeMap<Vec<u8>, Self>> for Value {
+    fn from(dict: BTreeMap<Vec<u8>, Self>) -> Self {
+        Self::Dict(dict)
+    }
+}
+
+/// Converts any serializable value into a [`Value`]
+///
+/// # Errors
+/// Fails if serialization fails
+pub fn to_value<T>(value: &T) -> Result<Value, BencodingSerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(Serializer::new())
+}
+
+/// Converts any serializable value into a [`Value`], converting types that
+/// bencode has no equivalent for according to the given config
+///
+/// # Errors
+/// Fails if serialization fails
+pub fn to_value_with_config<T>(value: &T, config: Config) -> Result<Value, BencodingSerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(Serializer::new().with_config(config))
+}
+
+/// Interprets a [`Value`] as an instance of type `T`
+///
+/// # Errors
+/// Fails if the value doesn't match the shape of `T`
+pub fn from_value<T>(value: Value) -> Result<T, BencodingDeserializeError>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(value)
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use super::Value;
+use crate::de::{from_bytes, BencodingDeserializeError};
+use crate::ser::{to_canonical_vec, BencodingSerializeError};
+
+// The name of the newtype struct `RawBencode` is (de)serialized as, which the
+// crate's serializers and deserializer recognise so that the raw bytes are
+// copied rather than encoded as a byte string
+pub const TOKEN: &str = "$bencoding::private::RawBencode";
+
+/// The exact bytes of a single bencoded value, kept without being decoded
+///
+/// Deserializing a `RawBencode` copies the value's bytes out of the input
+/// unchanged, and serializing one writes them back out as they are, so that
+/// something such as a torrent's `info` dictionary can be hashed exactly as it
+/// was encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RawBencode(Vec<u8>);
+
+impl RawBencode {
+    /// Checks that the bytes are exactly one bencoded value
+    ///
+    /// # Errors
+    /// Fails if the bytes aren't valid bencode, or have bytes after the value
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, BencodingDeserializeError> {
+        from_bytes::<de::IgnoredAny>(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    /// Encodes a value canonically, with every dictionary sorted by key
+    ///
+    /// # Errors
+    /// Fails if serialization fails
+    pub fn from_value<T>(value: &T) -> Result<Self, BencodingSerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        to_canonical_vec(value).map(Self)
+    }
+
+    /// Decodes the bytes as an instance of type `T`
+    ///
+    /// # Errors
+    /// Fails if the value doesn't match the shape of `T`
+    pub fn decode<'de, T>(&'de self) -> Result<T, BencodingDeserializeError>
+    where
+        T: Deserialize<'de>,
+    {
+        from_bytes(&self.0)
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Serialize for RawBencode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, &RawBytes(&self.0))
+    }
+}
+
+// The bytes inside the newtype, which other serializers will see as a byte
+// string
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawBencode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, RawBencodeVisitor)
+    }
+}
+
+struct RawBencodeVisitor;
+
+impl<'de> Visitor<'de> for RawBencodeVisitor {
+    type Value = RawBencode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any bencoded value")
+    }
+
+    // The crate's deserializer hands over the raw bytes of the value
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<RawBencode, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawBencode(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<RawBencode, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawBencode(v))
+    }
+
+    // Any other deserializer, such as a `Value`, gives the value itself, which
+    // is encoded canonically
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<RawBencode, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        RawBencode::from_value(&value).map_err(de::Error::custom)
+    }
+}
+
+// Recovers the raw bytes from the newtype that `RawBencode` serializes as
+pub fn raw_bytes<T>(value: &T) -> Result<Vec<u8>, BencodingSerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    match value.serialize(super::Serializer::new())? {
+        Value::Bytes(bytes) => Ok(bytes),
+        _ => Err(ser::Error::custom("expected the raw bytes of a value")),
+    }
+}
@@ -0,0 +1,757 @@
+use std::collections::{btree_map, BTreeMap};
+use std::convert::TryFrom;
+
+use serde::ser::{self, Error as _, Serialize};
+
+use super::{raw_bytes, Value, RAW_TOKEN};
+use crate::config::{BoolEncoding, Config, NoneEncoding, UnitEncoding};
+use crate::de::from_bytes;
+use crate::ser::{BencodingSerializeError, MapKeySerializer};
+
+type Result<T> = std::result::Result<T, BencodingSerializeError>;
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            Self::Int(i) => serializer.serialize_i64(*i),
+            Self::Bytes(bytes) => serializer.serialize_bytes(bytes),
+            Self::List(list) => serializer.collect_seq(list),
+            Self::Dict(dict) => serializer.collect_map(dict.iter().map(|(k, v)| (RawBytes(k), v))),
+        }
+    }
+}
+
+// Dictionary keys have to be serialized as byte strings, rather than as the
+// list of integers that `Vec<u8>` serializes as.
+pub(super) struct RawBytes<'a>(pub(super) &'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// A serializer whose output is a [`Value`], used by [`to_value`](super::to_value)
+#[derive(Debug, Clone, Copy)]
+pub struct Serializer {
+    config: Config,
+}
+
+impl Serializer {
+    /// Creates a new serializer, with the default config
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            config: Config::new(),
+        }
+    }
+
+    /// Sets how types that bencode has no equivalent for are converted
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeDict;
+    type SerializeStruct = SerializeDict;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        match self.config.bool_encoding {
+            BoolEncoding::Error => Err(BencodingSerializeError::UnsupportedType("bool")),
+            BoolEncoding::Int => self.serialize_i64(i64::from(v)),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| BencodingSerializeError::custom("integer out of range for a value"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Value> {
+        Err(BencodingSerializeError::UnsupportedType("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Value> {
+        Err(BencodingSerializeError::UnsupportedType("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Err(BencodingSerializeError::UnsupportedType("none"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        match self.config.unit_encoding {
+            UnitEncoding::Error => Err(BencodingSerializeError::UnsupportedType("unit")),
+            UnitEncoding::EmptyString => self.serialize_bytes(b""),
+        }
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        match self.config.unit_encoding {
+            UnitEncoding::Error => Err(BencodingSerializeError::UnsupportedType("unit struct")),
+            UnitEncoding::EmptyString => self.serialize_bytes(b""),
+        }
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        self.serialize_str(variant)
+    }
+
+    // A `RawBencode` is decoded into the value it holds
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == RAW_TOKEN {
+            return from_bytes(&raw_bytes(value)?).map_err(BencodingSerializeError::custom);
+        }
+
+        value.serialize(self)
+    }
+
+    // Variants with data are externally tagged, as `{ NAME: VALUE }`
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut dict = BTreeMap::new();
+        dict.insert(variant.as_bytes().to_vec(), value.serialize(self)?);
+        Ok(Value::Dict(dict))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeList {
+            ser: self,
+            list: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeTupleVariant {
+            ser: self,
+            variant,
+            list: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeDict {
+            ser: self,
+            dict: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeStructVariant {
+            ser: self,
+            variant,
+            dict: BTreeMap::new(),
+        })
+    }
+}
+
+pub struct SerializeList {
+    ser: Serializer,
+    list: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.list.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::List(self.list))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeTupleVariant {
+    ser: Serializer,
+    variant: &'static str,
+    list: Vec<Value>,
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.list.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        let mut dict = BTreeMap::new();
+        dict.insert(self.variant.as_bytes().to_vec(), Value::List(self.list));
+        Ok(Value::Dict(dict))
+    }
+}
+
+// Adds an entry to a dictionary, which can only have each key once
+fn insert_entry(dict: &mut BTreeMap<Vec<u8>, Value>, key: Vec<u8>, value: Value) -> Result<()> {
+    match dict.entry(key) {
+        btree_map::Entry::Vacant(entry) => {
+            entry.insert(value);
+            Ok(())
+        }
+        btree_map::Entry::Occupied(entry) => Err(BencodingSerializeError::DuplicateKey(
+            String::from_utf8_lossy(entry.key()).into_owned(),
+        )),
+    }
+}
+
+pub struct SerializeDict {
+    ser: Serializer,
+    dict: BTreeMap<Vec<u8>, Value>,
+    next_key: Option<Vec<u8>>,
+}
+
+impl ser::SerializeMap for SerializeDict {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        // Only byte strings are valid dictionary keys
+        self.next_key = Some(key.serialize(MapKeySerializer::new(self.ser.config))?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.next_key.take().ok_or_else(|| {
+            BencodingSerializeError::custom("serialize_value called before serialize_key")
+        })?;
+        if let Some(value) = value.serialize(DictValue(self.ser))? {
+            insert_entry(&mut self.dict, key, value)?;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Dict(self.dict))
+    }
+}
+
+impl ser::SerializeStruct for SerializeDict {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some(value) = value.serialize(DictValue(self.ser))? {
+            insert_entry(&mut self.dict, key.as_bytes().to_vec(), value)?;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+pub struct SerializeStructVariant {
+    ser: Serializer,
+    variant: &'static str,
+    dict: BTreeMap<Vec<u8>, Value>,
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = Value;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some(value) = value.serialize(DictValue(self.ser))? {
+            insert_entry(&mut self.dict, key.as_bytes().to_vec(), value)?;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        let mut dict = BTreeMap::new();
+        dict.insert(self.variant.as_bytes().to_vec(), Value::Dict(self.dict));
+        Ok(Value::Dict(dict))
+    }
+}
+
+// Converts a dictionary value, which is `None` if it's left out, as `None` is
+// by the byte serializer. Everything else is converted as it is by the
+// serializer it wraps.
+struct DictValue(Serializer);
+
+impl ser::Serializer for DictValue {
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    type SerializeSeq = Present<SerializeList>;
+    type SerializeTuple = Present<SerializeList>;
+    type SerializeTupleStruct = Present<SerializeList>;
+    type SerializeTupleVariant = Present<SerializeTupleVariant>;
+    type SerializeMap = Present<SerializeDict>;
+    type SerializeStruct = Present<SerializeDict>;
+    type SerializeStructVariant = Present<SerializeStructVariant>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>> {
+        self.0.serialize_bool(v).map(Some)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>> {
+        self.0.serialize_i8(v).map(Some)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>> {
+        self.0.serialize_i16(v).map(Some)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>> {
+        self.0.serialize_i32(v).map(Some)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>> {
+        self.0.serialize_i64(v).map(Some)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>> {
+        self.0.serialize_u8(v).map(Some)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>> {
+        self.0.serialize_u16(v).map(Some)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>> {
+        self.0.serialize_u32(v).map(Some)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>> {
+        self.0.serialize_u64(v).map(Some)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<Value>> {
+        self.0.serialize_f32(v).map(Some)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<Value>> {
+        self.0.serialize_f64(v).map(Some)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>> {
+        self.0.serialize_char(v).map(Some)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>> {
+        self.0.serialize_str(v).map(Some)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>> {
+        self.0.serialize_bytes(v).map(Some)
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>> {
+        match self.0.config.none_encoding {
+            NoneEncoding::Omit => Ok(None),
+            NoneEncoding::Error => self.0.serialize_none().map(Some),
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Option<Value>>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>> {
+        self.0.serialize_unit().map(Some)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Option<Value>> {
+        self.0.serialize_unit_struct(name).map(Some)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Option<Value>> {
+        self.0
+            .serialize_unit_variant(name, variant_index, variant)
+            .map(Some)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Option<Value>>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_newtype_struct(name, value).map(Some)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0
+            .serialize_newtype_variant(name, variant_index, variant, value)
+            .map(Some)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.0.serialize_seq(len).map(Present)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.0.serialize_tuple(len).map(Present)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.0.serialize_tuple_struct(name, len).map(Present)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.0
+            .serialize_tuple_variant(name, variant_index, variant, len)
+            .map(Present)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.0.serialize_map(len).map(Present)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.0.serialize_struct(name, len).map(Present)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.0
+            .serialize_struct_variant(name, variant_index, variant, len)
+            .map(Present)
+    }
+}
+
+// A list or dictionary that is a dictionary value, which is never left out
+struct Present<S>(S);
+
+impl<S> ser::SerializeSeq for Present<S>
+where
+    S: ser::SerializeSeq<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeTuple for Present<S>
+where
+    S: ser::SerializeTuple<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeTupleStruct for Present<S>
+where
+    S: ser::SerializeTupleStruct<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeTupleVariant for Present<S>
+where
+    S: ser::SerializeTupleVariant<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeMap for Present<S>
+where
+    S: ser::SerializeMap<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_key(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_value(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeStruct for Present<S>
+where
+    S: ser::SerializeStruct<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
+
+impl<S> ser::SerializeStructVariant for Present<S>
+where
+    S: ser::SerializeStructVariant<Ok = Value, Error = BencodingSerializeError>,
+{
+    type Ok = Option<Value>;
+    type Error = BencodingSerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        self.0.end().map(Some)
+    }
+}
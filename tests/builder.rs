@@ -0,0 +1,252 @@
+mod files;
+
+use std::fs;
+
+use bencoding::torrent::{
+    FileTreeNode, Metainfo, TorrentBuilder, TorrentError, TorrentVersion, V2File,
+};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use files::{contents, temp_dir};
+
+const BLOCK: usize = 16 * 1024;
+
+fn sha1_pieces(bytes: &[u8], piece_length: usize) -> Vec<u8> {
+    bytes
+        .chunks(piece_length)
+        .flat_map(|piece| Sha1::digest(piece).to_vec())
+        .collect()
+}
+
+fn sha256_pair(left: &[u8], right: &[u8]) -> [u8; 32] {
+    Sha256::digest([left, right].concat()).into()
+}
+
+#[test]
+fn single_file() {
+    let dir = temp_dir("single");
+    let path = dir.join("alice.txt");
+    let data = contents(3 * BLOCK + 100, 7);
+    fs::write(&path, &data).unwrap();
+
+    let torrent = TorrentBuilder::new(&path)
+        .announce("http://tracker.example.com:6969/announce")
+        .announce_list(vec![vec!["udp://tracker.example.com:6969".to_string()]])
+        .comment("Alice's Adventures in Wonderland")
+        .created_by(Some("bencoding".to_string()))
+        .creation_date(Some(1_600_000_000))
+        .private(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        Some("http://tracker.example.com:6969/announce"),
+        torrent.announce.as_deref()
+    );
+    assert_eq!(Some(1), torrent.announce_list.as_ref().map(Vec::len));
+    assert_eq!(
+        Some("Alice's Adventures in Wonderland"),
+        torrent.comment.as_deref()
+    );
+    assert_eq!(Some("bencoding"), torrent.created_by.as_deref());
+    assert_eq!(Some(1_600_000_000), torrent.creation_date);
+
+    let info = torrent.info();
+    assert_eq!("alice.txt", info.name);
+    assert_eq!(BLOCK as u64, info.piece_length);
+    assert_eq!(Some(data.len() as u64), info.length);
+    assert!(info.is_v1());
+    assert!(!info.is_v2());
+    assert!(info.is_private());
+    assert_eq!(4, info.piece_count());
+    assert_eq!(Some(sha1_pieces(&data, BLOCK)), info.pieces);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn multi_file() {
+    let dir = temp_dir("multi");
+    let root = dir.join("book");
+    fs::create_dir_all(root.join("chapters")).unwrap();
+    let first = contents(BLOCK + 10, 1);
+    let second = contents(2 * BLOCK, 2);
+    let third = contents(5, 3);
+    fs::write(root.join("chapters").join("1.txt"), &first).unwrap();
+    fs::write(root.join("chapters").join("2.txt"), &second).unwrap();
+    fs::write(root.join("cover.txt"), &third).unwrap();
+
+    let torrent = TorrentBuilder::new(&root)
+        .piece_length(2 * BLOCK as u64)
+        .build()
+        .unwrap();
+    let info = torrent.info();
+
+    assert_eq!("book", info.name);
+    assert!(info.is_multi_file());
+    assert_eq!(None, info.length);
+
+    let files = info.files.as_ref().unwrap();
+    let paths: Vec<_> = files.iter().map(|file| file.path.join("/")).collect();
+    assert_eq!(vec!["chapters/1.txt", "chapters/2.txt", "cover.txt"], paths);
+
+    // v1 pieces span file boundaries
+    let all = [first, second, third].concat();
+    assert_eq!(Some(sha1_pieces(&all, 2 * BLOCK)), info.pieces);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn v2_only() {
+    let dir = temp_dir("v2");
+    let path = dir.join("data.bin");
+    let data = contents(3 * BLOCK + 1, 9);
+    fs::write(&path, &data).unwrap();
+
+    let torrent = TorrentBuilder::new(&path)
+        .version(TorrentVersion::V2)
+        .piece_length(2 * BLOCK as u64)
+        .build()
+        .unwrap();
+    let info = torrent.info();
+
+    assert!(!info.is_v1());
+    assert!(info.is_v2());
+    assert_eq!(None, info.pieces);
+    assert_eq!(Some(2), info.meta_version);
+
+    // Four leaves, the last of which hashes a partial block
+    let leaves: Vec<[u8; 32]> = data
+        .chunks(BLOCK)
+        .map(|block| Sha256::digest(block).into())
+        .collect();
+    let left = sha256_pair(&leaves[0], &leaves[1]);
+    let right = sha256_pair(&leaves[2], &leaves[3]);
+    let root = sha256_pair(&left, &right);
+
+    let tree = info.file_tree.as_ref().unwrap();
+    assert_eq!(
+        Some(&FileTreeNode::File(V2File {
+            length: data.len() as u64,
+            pieces_root: Some(root),
+        })),
+        tree.0.get("data.bin")
+    );
+    assert_eq!(
+        Some(&[left, right].concat()[..]),
+        torrent.piece_layer(&root)
+    );
+    assert!(torrent.info_hash_v2().is_some());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn hybrid() {
+    let dir = temp_dir("hybrid");
+    let root = dir.join("pair");
+    fs::create_dir_all(&root).unwrap();
+    let first = contents(BLOCK + 1, 4);
+    let second = contents(10, 5);
+    let empty = Vec::new();
+    fs::write(root.join("a"), &first).unwrap();
+    fs::write(root.join("b"), &second).unwrap();
+    fs::write(root.join("c"), &empty).unwrap();
+
+    let torrent = TorrentBuilder::new(&root)
+        .version(TorrentVersion::Hybrid)
+        .piece_length(BLOCK as u64)
+        .build()
+        .unwrap();
+    let info = torrent.info();
+
+    assert!(info.is_hybrid());
+
+    // Every file but the last is padded to the end of its last piece
+    let files = info.files.as_ref().unwrap();
+    assert_eq!(5, files.len());
+    assert!(files[1].is_padding());
+    assert_eq!(BLOCK as u64 - 1, files[1].length);
+    assert!(files[3].is_padding());
+    assert_eq!(BLOCK as u64 - 10, files[3].length);
+    assert!(!files[4].is_padding());
+
+    let padded = [
+        &first[..],
+        &vec![0; BLOCK - 1],
+        &second,
+        &vec![0; BLOCK - 10],
+    ]
+    .concat();
+    assert_eq!(Some(sha1_pieces(&padded, BLOCK)), info.pieces);
+
+    let tree = info.file_tree.as_ref().unwrap();
+    let v2_files = tree.files();
+    assert_eq!(3, v2_files.len());
+    assert_eq!(None, v2_files[2].1.pieces_root);
+
+    // Encoding and decoding the torrent keeps its info-hashes
+    let mut encoded = Vec::new();
+    let written = TorrentBuilder::new(&root)
+        .version(TorrentVersion::Hybrid)
+        .piece_length(BLOCK as u64)
+        .creation_date(None)
+        .write(&mut encoded)
+        .unwrap();
+    let decoded: Metainfo = bencoding::from_bytes(&encoded).unwrap();
+    assert_eq!(written, decoded);
+    assert_eq!(torrent.info_hash_v1(), decoded.info_hash_v1());
+    assert_eq!(torrent.info_hash_v2(), decoded.info_hash_v2());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_skipped() {
+    use std::os::unix::fs::symlink;
+
+    let dir = temp_dir("symlinks");
+    let root = dir.join("book");
+    fs::create_dir_all(&root).unwrap();
+    let data = contents(10, 1);
+    fs::write(root.join("cover.txt"), &data).unwrap();
+    fs::write(dir.join("secret.txt"), b"outside").unwrap();
+
+    // A link back to the root would otherwise be walked forever, and one out
+    // of it would put files from elsewhere in the torrent
+    symlink(&root, root.join("loop")).unwrap();
+    symlink(dir.join("secret.txt"), root.join("secret.txt")).unwrap();
+
+    let torrent = TorrentBuilder::new(&root).build().unwrap();
+    let files = torrent.info().files.as_ref().unwrap();
+    let paths: Vec<_> = files.iter().map(|file| file.path.join("/")).collect();
+    assert_eq!(vec!["cover.txt"], paths);
+    assert_eq!(Some(sha1_pieces(&data, BLOCK)), torrent.info().pieces);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid() {
+    let dir = temp_dir("invalid");
+
+    assert!(matches!(
+        TorrentBuilder::new(&dir).build(),
+        Err(TorrentError::NoFiles)
+    ));
+
+    fs::write(dir.join("file"), b"data").unwrap();
+    assert!(matches!(
+        TorrentBuilder::new(&dir).piece_length(1000).build(),
+        Err(TorrentError::InvalidPieceLength(1000))
+    ));
+    assert!(matches!(
+        TorrentBuilder::new(dir.join("missing")).build(),
+        Err(TorrentError::Io(_))
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}
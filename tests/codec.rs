@@ -0,0 +1,321 @@
+#![cfg(feature = "tokio-codec")]
+
+use std::convert::TryFrom;
+
+use bencoding::codec::{BencodeCodec, CodecError, LengthPrefixedCodec};
+use bencoding::de::{BencodingDeserializeError, DEFAULT_MAX_DEPTH};
+use bencoding::dht::{KrpcBody, KrpcMessage, Query};
+use bencoding::{DecoderOptions, RawBencode};
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+const PING: &[u8] = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe";
+
+#[test]
+fn partial_input() {
+    let mut codec = BencodeCodec::<KrpcMessage>::new();
+    let mut buffer = BytesMut::new();
+
+    // Every prefix of the message is incomplete
+    for &byte in &PING[..PING.len() - 1] {
+        buffer.extend_from_slice(&[byte]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+    }
+    assert_eq!(PING.len() - 1, buffer.len());
+
+    buffer.extend_from_slice(&PING[PING.len() - 1..]);
+    let message = codec.decode(&mut buffer).unwrap().unwrap();
+    assert_eq!(
+        KrpcBody::Query(Query::Ping {
+            id: *b"abcdefghij0123456789"
+        }),
+        message.body
+    );
+    assert!(buffer.is_empty());
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+}
+
+#[test]
+fn split_anywhere() {
+    let input: &[u8] = b"i-12e4:spamli1ei-3eed1:ai-4e1:bld1:cleeee0:";
+    let values = [
+        &b"i-12e"[..],
+        b"4:spam",
+        b"li1ei-3ee",
+        b"d1:ai-4e1:bld1:cleeee",
+        b"0:",
+    ];
+
+    // However the input is split in two, the same values are decoded
+    for split in 0..=input.len() {
+        let mut codec = BencodeCodec::<RawBencode>::new();
+        let mut buffer = BytesMut::from(&input[..split]);
+        let mut decoded = Vec::new();
+
+        while let Some(value) = codec.decode(&mut buffer).unwrap() {
+            decoded.push(value.into_bytes());
+        }
+        buffer.extend_from_slice(&input[split..]);
+        while let Some(value) = codec.decode(&mut buffer).unwrap() {
+            decoded.push(value.into_bytes());
+        }
+
+        assert_eq!(values.to_vec(), decoded, "split at byte {split}");
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn nesting_limit() {
+    let mut codec = BencodeCodec::<RawBencode>::new();
+
+    // Deep nesting is an error rather than overflowing the stack
+    let mut buffer = BytesMut::from(&vec![b'l'; 200_000][..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(
+            BencodingDeserializeError::DepthLimitExceeded(DEFAULT_MAX_DEPTH)
+        ))
+    ));
+
+    let mut nested = vec![b'l'; DEFAULT_MAX_DEPTH];
+    nested.extend(vec![b'e'; DEFAULT_MAX_DEPTH]);
+    let mut buffer = BytesMut::from(&nested[..]);
+    assert_eq!(
+        nested,
+        codec.decode(&mut buffer).unwrap().unwrap().into_bytes()
+    );
+}
+
+#[test]
+fn options() {
+    let options = DecoderOptions {
+        max_depth: 2,
+        ..DecoderOptions::strict()
+    };
+    let mut codec = BencodeCodec::<RawBencode>::new().with_options(options);
+    assert_eq!(options, codec.options());
+
+    // The codec's nesting limit is the one decoding with the options has
+    let mut buffer = BytesMut::from(&b"llee"[..]);
+    assert!(codec.decode(&mut buffer).unwrap().is_some());
+    let mut buffer = BytesMut::from(&b"llle"[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(
+            BencodingDeserializeError::DepthLimitExceeded(2)
+        ))
+    ));
+
+    // Strict decoding applies to whole values
+    let mut buffer = BytesMut::from(&b"i03e"[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(e)) if matches!(e.kind(), BencodingDeserializeError::LeadingZero)
+    ));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn consecutive_values() {
+    let mut codec = BencodeCodec::<RawBencode>::new();
+    let mut buffer = BytesMut::from(&b"i1e4:spamli2eed1:xi3eei4"[..]);
+
+    let mut values = Vec::new();
+    while let Some(value) = codec.decode(&mut buffer).unwrap() {
+        values.push(value.as_bytes().to_vec());
+    }
+    assert_eq!(
+        vec![
+            b"i1e".to_vec(),
+            b"4:spam".to_vec(),
+            b"li2ee".to_vec(),
+            b"d1:xi3ee".to_vec()
+        ],
+        values
+    );
+    // The start of the next value is kept
+    assert_eq!(&b"i4"[..], &buffer[..]);
+}
+
+#[test]
+fn invalid_values() {
+    let mut codec = BencodeCodec::<KrpcMessage>::new();
+
+    // A whole value that isn't a valid message is consumed
+    let mut buffer = BytesMut::from(&b"i1e"[..]);
+    buffer.extend_from_slice(PING);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(_))
+    ));
+    assert!(codec.decode(&mut buffer).unwrap().is_some());
+
+    // Input that isn't bencode at all
+    let mut buffer = BytesMut::from(&b"x"[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(_))
+    ));
+}
+
+#[test]
+fn max_length() {
+    let mut codec = BencodeCodec::<RawBencode>::with_max_length(8);
+    assert_eq!(8, codec.max_length());
+
+    let mut buffer = BytesMut::from(&b"5:short"[..]);
+    assert!(codec.decode(&mut buffer).unwrap().is_some());
+
+    // Whether or not the value is complete
+    let mut buffer = BytesMut::from(&b"10:too long"[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::TooLong(8))
+    ));
+    let mut buffer = BytesMut::from(&b"100:too long"[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::TooLong(8))
+    ));
+
+    let mut buffer = BytesMut::from(&b"i1e"[..]);
+    assert!(matches!(
+        codec.encode("too long", &mut buffer),
+        Err(CodecError::TooLong(8))
+    ));
+    assert_eq!(&b"i1e"[..], &buffer[..]);
+}
+
+#[test]
+fn encode() {
+    let mut codec = BencodeCodec::<KrpcMessage>::new();
+    let message: KrpcMessage = bencoding::from_bytes(PING).unwrap();
+
+    let mut buffer = BytesMut::new();
+    codec.encode(&message, &mut buffer).unwrap();
+    codec.encode(message.clone(), &mut buffer).unwrap();
+    assert_eq!([PING, PING].concat(), &buffer[..]);
+
+    assert_eq!(Some(message.clone()), codec.decode(&mut buffer).unwrap());
+    assert_eq!(Some(message), codec.decode(&mut buffer).unwrap());
+}
+
+fn length_prefixed(value: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(value.len()).unwrap();
+    [&len.to_be_bytes()[..], value].concat()
+}
+
+#[test]
+fn length_prefixed_partial_input() {
+    let mut codec = LengthPrefixedCodec::<KrpcMessage>::new();
+    let frame = length_prefixed(PING);
+    let mut buffer = BytesMut::new();
+
+    // Every prefix of the frame is incomplete, including the length itself
+    for &byte in &frame[..frame.len() - 1] {
+        buffer.extend_from_slice(&[byte]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+    }
+    buffer.extend_from_slice(&frame[frame.len() - 1..]);
+
+    let message = codec.decode(&mut buffer).unwrap().unwrap();
+    assert_eq!(b"aa", &message.transaction_id[..]);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn length_prefixed_frames() {
+    let mut codec = LengthPrefixedCodec::<RawBencode>::new();
+    let mut buffer = BytesMut::new();
+    buffer.extend_from_slice(&length_prefixed(b"i1e"));
+    buffer.extend_from_slice(&length_prefixed(b"4:spam"));
+    buffer.extend_from_slice(&length_prefixed(b"i2ei3e"));
+    buffer.extend_from_slice(&length_prefixed(b"le"));
+
+    assert_eq!(
+        &b"i1e"[..],
+        codec.decode(&mut buffer).unwrap().unwrap().as_bytes()
+    );
+    assert_eq!(
+        &b"4:spam"[..],
+        codec.decode(&mut buffer).unwrap().unwrap().as_bytes()
+    );
+
+    // A frame must hold exactly one value, but it's consumed either way
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(e)) if matches!(e.kind(), BencodingDeserializeError::TrailingCharacters)
+    ));
+    assert_eq!(
+        &b"le"[..],
+        codec.decode(&mut buffer).unwrap().unwrap().as_bytes()
+    );
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+}
+
+#[test]
+fn length_prefixed_options() {
+    let options = DecoderOptions {
+        max_depth: 2,
+        ..DecoderOptions::strict()
+    };
+    let mut codec = LengthPrefixedCodec::<RawBencode>::new().with_options(options);
+    assert_eq!(options, codec.options());
+
+    let mut buffer = BytesMut::from(&length_prefixed(b"llee")[..]);
+    assert!(codec.decode(&mut buffer).unwrap().is_some());
+
+    let mut buffer = BytesMut::from(&length_prefixed(b"llleee")[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(e)) if matches!(e.kind(), BencodingDeserializeError::DepthLimitExceeded(2))
+    ));
+
+    let mut buffer = BytesMut::from(&length_prefixed(b"i03e")[..]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::Deserialize(e)) if matches!(e.kind(), BencodingDeserializeError::LeadingZero)
+    ));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn length_prefixed_max_length() {
+    let mut codec = LengthPrefixedCodec::<RawBencode>::with_max_length(8);
+    assert_eq!(8, codec.max_length());
+
+    let mut buffer = BytesMut::from(&length_prefixed(b"5:short")[..]);
+    assert!(codec.decode(&mut buffer).unwrap().is_some());
+
+    // Rejected from the prefix alone, before the value arrives
+    let mut buffer = BytesMut::from(&length_prefixed(b"10:too long")[..4]);
+    assert!(matches!(
+        codec.decode(&mut buffer),
+        Err(CodecError::TooLong(8))
+    ));
+
+    let mut buffer = BytesMut::from(&b"i1e"[..]);
+    assert!(matches!(
+        codec.encode("too long", &mut buffer),
+        Err(CodecError::TooLong(8))
+    ));
+    assert_eq!(&b"i1e"[..], &buffer[..]);
+}
+
+#[test]
+fn length_prefixed_encode() {
+    let mut codec = LengthPrefixedCodec::<KrpcMessage>::new();
+    let message: KrpcMessage = bencoding::from_bytes(PING).unwrap();
+
+    let mut buffer = BytesMut::new();
+    codec.encode(&message, &mut buffer).unwrap();
+    codec.encode(message.clone(), &mut buffer).unwrap();
+    assert_eq!(
+        [length_prefixed(PING), length_prefixed(PING)].concat(),
+        &buffer[..]
+    );
+
+    assert_eq!(Some(message.clone()), codec.decode(&mut buffer).unwrap());
+    assert_eq!(Some(message), codec.decode(&mut buffer).unwrap());
+}
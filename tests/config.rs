@@ -0,0 +1,286 @@
+use bencoding::de::{BencodingDeserializeError, Deserializer};
+use bencoding::ser::{BencodingSerializeError, Serializer};
+use bencoding::{
+    from_bytes, from_bytes_with_config, to_value, to_value_with_config, to_vec, to_vec_with_config,
+    BoolEncoding, Config, DecoderOptions, IntKeyEncoding, NoneEncoding, UnitEncoding,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+struct Peer {
+    id: String,
+    port: Option<u16>,
+    seed: Option<bool>,
+    flags: Vec<Option<u8>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+struct Marker;
+
+const INT_BOOLS: Config = Config {
+    bool_encoding: BoolEncoding::Int,
+    ..Config::new()
+};
+
+#[test]
+fn bools_as_integers() {
+    assert_eq!(
+        b"i1e".to_vec(),
+        to_vec_with_config(&true, INT_BOOLS).unwrap()
+    );
+    assert_eq!(
+        b"i0e".to_vec(),
+        to_vec_with_config(&false, INT_BOOLS).unwrap()
+    );
+
+    assert!(from_bytes_with_config::<bool>(b"i1e", INT_BOOLS).unwrap());
+    assert!(!from_bytes_with_config::<bool>(b"i0e", INT_BOOLS).unwrap());
+    assert!(matches!(
+        from_bytes_with_config::<bool>(b"i2e", INT_BOOLS)
+            .map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::SerdeDeserializeError(_))
+    ));
+
+    assert!(matches!(
+        from_bytes::<bool>(b"i1e").map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::InvalidTypeBool)
+    ));
+}
+
+#[test]
+fn none_fields_omitted() {
+    let peer = Peer {
+        id: "abc".to_string(),
+        port: None,
+        seed: Some(true),
+        flags: vec![Some(1)],
+    };
+    let encoded = b"d2:id3:abc4:seedi1e5:flagsli1eee".to_vec();
+
+    assert_eq!(encoded, to_vec_with_config(&peer, INT_BOOLS).unwrap());
+    assert_eq!(peer, from_bytes_with_config(&encoded, INT_BOOLS).unwrap());
+
+    let mut serializer = Serializer::canonical(Vec::new()).with_config(INT_BOOLS);
+    peer.serialize(&mut serializer).unwrap();
+
+    assert_eq!(
+        b"d5:flagsli1ee2:id3:abc4:seedi1ee".to_vec(),
+        serializer.into_inner()
+    );
+
+    // The last field being left out still closes the dictionary
+    let peer = Peer {
+        id: "abc".to_string(),
+        port: Some(6881),
+        seed: None,
+        flags: Vec::new(),
+    };
+
+    assert_eq!(
+        b"d2:id3:abc4:porti6881e5:flagslee".to_vec(),
+        to_vec(&peer).unwrap()
+    );
+
+    // `None` can only be left out of a dictionary
+    let peer = Peer {
+        id: "abc".to_string(),
+        port: None,
+        seed: None,
+        flags: vec![None],
+    };
+
+    assert!(matches!(
+        to_vec(&peer),
+        Err(BencodingSerializeError::UnsupportedType("none"))
+    ));
+}
+
+#[test]
+fn none_as_error() {
+    let config = Config {
+        none_encoding: NoneEncoding::Error,
+        ..Config::default()
+    };
+
+    assert!(matches!(
+        to_vec_with_config(&Some(5), config),
+        Ok(encoded) if encoded == b"i5e"
+    ));
+    assert!(matches!(
+        to_vec_with_config(&None::<u8>, config),
+        Err(BencodingSerializeError::UnsupportedType("none"))
+    ));
+}
+
+#[test]
+fn unit_as_empty_string() {
+    let config = Config {
+        unit_encoding: UnitEncoding::EmptyString,
+        ..Config::default()
+    };
+
+    assert_eq!(b"0:".to_vec(), to_vec_with_config(&(), config).unwrap());
+    assert_eq!(
+        b"l0:e".to_vec(),
+        to_vec_with_config(&[Marker], config).unwrap()
+    );
+
+    from_bytes_with_config::<()>(b"0:", config).unwrap();
+    assert_eq!(
+        vec![Marker],
+        from_bytes_with_config::<Vec<Marker>>(b"l0:e", config).unwrap()
+    );
+    assert!(matches!(
+        from_bytes_with_config::<()>(b"1:a", config).map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::ExpectedNull)
+    ));
+
+    assert!(matches!(
+        to_vec(&Marker),
+        Err(BencodingSerializeError::UnsupportedType("unit struct"))
+    ));
+    assert!(matches!(
+        from_bytes::<()>(b"0:").map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::InvalidTypeUnit)
+    ));
+}
+
+#[test]
+fn integer_keys() {
+    use std::collections::BTreeMap;
+
+    let config = Config {
+        int_key_encoding: IntKeyEncoding::String,
+        ..Config::default()
+    };
+    let map = BTreeMap::from([(2_u32, "b"), (10, "j"), (1, "a")]);
+
+    assert_eq!(
+        b"d1:11:a1:21:b2:101:je".to_vec(),
+        to_vec_with_config(&map, config).unwrap()
+    );
+    assert_eq!(
+        map,
+        from_bytes_with_config(b"d1:11:a1:21:b2:101:je", config).unwrap()
+    );
+
+    let mut serializer = Serializer::canonical(Vec::new()).with_config(config);
+    map.serialize(&mut serializer).unwrap();
+
+    // Canonical order sorts the digits as bytes, not as numbers
+    assert_eq!(b"d1:11:a2:101:j1:21:be".to_vec(), serializer.into_inner());
+
+    let signed = BTreeMap::from([(-5_i64, 1)]);
+
+    assert_eq!(
+        signed,
+        from_bytes_with_config(&to_vec_with_config(&signed, config).unwrap(), config).unwrap()
+    );
+    assert!(matches!(
+        from_bytes_with_config::<BTreeMap<u8, u8>>(b"d3:abci1ee", config)
+            .map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::ParseIntError)
+    ));
+
+    // Each integer has only the one key that it's encoded as
+    for (key, error) in [
+        (&b"d2:+5i1ee"[..], BencodingDeserializeError::ParseIntError),
+        (b"d3:007i1ee", BencodingDeserializeError::LeadingZero),
+        (b"d2:-0i1ee", BencodingDeserializeError::NegativeZero),
+        (b"d3:-05i1ee", BencodingDeserializeError::LeadingZero),
+        (b"d1:-i1ee", BencodingDeserializeError::ParseIntError),
+        (b"d0:i1ee", BencodingDeserializeError::ParseIntError),
+        (b"d2: 5i1ee", BencodingDeserializeError::ParseIntError),
+    ] {
+        let result = from_bytes_with_config::<BTreeMap<i64, u8>>(key, config)
+            .map_err(BencodingDeserializeError::into_kind);
+        assert_eq!(Some(error.to_string()), result.err().map(|e| e.to_string()));
+    }
+    assert_eq!(
+        BTreeMap::from([(0, 1), (-10, 2)]),
+        from_bytes_with_config(b"d1:0i1e3:-10i2ee", config).unwrap()
+    );
+
+    assert!(matches!(
+        to_vec(&map),
+        Err(BencodingSerializeError::NonStringKey)
+    ));
+    assert!(from_bytes::<BTreeMap<u32, String>>(b"d1:11:ae").is_err());
+}
+
+#[test]
+fn config_with_values() {
+    use std::collections::BTreeMap;
+
+    let peer = Peer {
+        id: "abc".to_string(),
+        port: None,
+        seed: Some(true),
+        flags: vec![Some(1)],
+    };
+    let value = to_value_with_config(&peer, INT_BOOLS).unwrap();
+
+    assert_eq!(
+        b"d5:flagsli1ee2:id3:abc4:seedi1ee".to_vec(),
+        to_vec(&value).unwrap()
+    );
+    assert_eq!(None, value.get("port"));
+    assert!(matches!(
+        to_value(&peer),
+        Err(BencodingSerializeError::UnsupportedType("bool"))
+    ));
+
+    let config = Config {
+        none_encoding: NoneEncoding::Error,
+        unit_encoding: UnitEncoding::EmptyString,
+        int_key_encoding: IntKeyEncoding::String,
+        ..INT_BOOLS
+    };
+
+    assert_eq!(
+        to_vec_with_config(&[Marker], config).unwrap(),
+        to_vec(&to_value_with_config(&[Marker], config).unwrap()).unwrap()
+    );
+    assert_eq!(
+        b"d1:1i1e2:10i0ee".to_vec(),
+        to_vec(&to_value_with_config(&BTreeMap::from([(1, true), (10, false)]), config).unwrap())
+            .unwrap()
+    );
+    assert!(matches!(
+        to_value_with_config(&peer, config),
+        Err(BencodingSerializeError::UnsupportedType("none"))
+    ));
+}
+
+#[test]
+fn config_with_other_entry_points() {
+    let peer = Peer {
+        id: "abc".to_string(),
+        port: None,
+        seed: Some(true),
+        flags: vec![Some(1)],
+    };
+    let encoded = b"d5:flagsli1ee2:id3:abc4:seedi1ee".to_vec();
+
+    let mut serializer = Serializer::canonical(Vec::new()).with_config(INT_BOOLS);
+    peer.serialize(&mut serializer).unwrap();
+    assert_eq!(encoded, serializer.into_inner());
+
+    let mut deserializer = Deserializer::from_reader(&encoded[..]).with_config(INT_BOOLS);
+    assert_eq!(peer, Peer::deserialize(&mut deserializer).unwrap());
+    deserializer.end().unwrap();
+
+    // Strict mode and a config can be used together
+    let strict = DecoderOptions::strict();
+
+    let mut deserializer = Deserializer::with_options(&encoded, strict).with_config(INT_BOOLS);
+    assert_eq!(peer, Peer::deserialize(&mut deserializer).unwrap());
+    deserializer.end().unwrap();
+
+    let mut deserializer = Deserializer::with_options(b"d2:id3:abc4:seedi1e5:flagsli1eee", strict)
+        .with_config(INT_BOOLS);
+    assert!(matches!(
+        Peer::deserialize(&mut deserializer),
+        Err(BencodingDeserializeError::UnsortedKey(_))
+    ));
+}
@@ -87,5 +87,66 @@ fn person_decode() {
 
 #[test]
 fn torrent_decode() {
-    let _file = include_bytes!("./alice.torrent");
+    use bencoding::torrent::Metainfo;
+
+    let file = include_bytes!("./alice.torrent");
+    let torrent: Metainfo = bencoding::from_bytes(file).unwrap();
+
+    assert_eq!("alice.txt", torrent.info().name);
+    assert_eq!(Some(36360), torrent.info().length);
+}
+
+#[test]
+fn error_location() {
+    use bencoding::de::BencodingDeserializeError;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct File {
+        #[allow(dead_code)]
+        length: u64,
+    }
+
+    let encoded = b"d4:infod5:filesld6:lengthi1eed6:lengthi-2eeeee";
+    let error = bencoding::from_bytes::<BTreeMap<String, BTreeMap<String, Vec<File>>>>(encoded)
+        .unwrap_err();
+
+    assert_eq!(Some("info.files[1].length"), error.path());
+    assert_eq!(Some(39), error.offset());
+    assert!(matches!(
+        error.kind(),
+        BencodingDeserializeError::ParseIntError
+    ));
+    assert_eq!(
+        "an error occurred while parsing an int at byte 39 in `info.files[1].length`, \
+         near `thi1eed6:lengthi-2eeeee`",
+        error.to_string()
+    );
+
+    let error = bencoding::from_str::<String>("4:spam4:eggs").unwrap_err();
+
+    assert_eq!(Some(6), error.offset());
+    assert_eq!(Some(""), error.path());
+    assert!(matches!(
+        error.into_kind(),
+        BencodingDeserializeError::TrailingCharacters
+    ));
+}
+
+#[test]
+fn decode_char() {
+    use bencoding::de::BencodingDeserializeError;
+
+    assert_eq!('a', bencoding::from_str::<char>("1:a").unwrap());
+    assert_eq!('é', bencoding::from_str::<char>("2:é").unwrap());
+    assert!(matches!(
+        bencoding::from_str::<char>("3:abc").map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::SerdeDeserializeError(_))
+    ));
+    assert_eq!("1:a", bencoding::to_string(&'a').unwrap());
+
+    assert!(matches!(
+        bencoding::from_str::<f64>("i1e").map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::InvalidTypeFloat)
+    ));
 }
@@ -0,0 +1,275 @@
+mod messages;
+
+use bencoding::dht::{
+    immutable_target, mutable_target, signature_buffer, KrpcBody, KrpcError, KrpcMessage, NodeInfo,
+    Query, Response, StorageError, MAX_SALT_LEN, MAX_VALUE_LEN,
+};
+use bencoding::RawBencode;
+
+use messages::{addr, round_trip};
+
+const ID: [u8; 20] = *b"abcdefghij0123456789";
+const OTHER: [u8; 20] = *b"mnopqrstuvwxyz123456";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn queries() {
+    let message: KrpcMessage =
+        round_trip(b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe");
+    assert_eq!(b"aa", &message.transaction_id[..]);
+    assert_eq!(KrpcBody::Query(Query::Ping { id: ID }), message.body);
+
+    let message: KrpcMessage = round_trip(
+        b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e1:q9:find_node1:t2:aa1:y1:qe",
+    );
+    assert_eq!(
+        KrpcBody::Query(Query::FindNode {
+            id: ID,
+            target: OTHER
+        }),
+        message.body
+    );
+
+    let message: KrpcMessage = round_trip(
+        b"d1:ad2:id20:abcdefghij01234567899:info_hash20:mnopqrstuvwxyz123456e1:q9:get_peers1:t2:aa1:y1:qe",
+    );
+    assert_eq!(
+        KrpcBody::Query(Query::GetPeers {
+            id: ID,
+            info_hash: OTHER
+        }),
+        message.body
+    );
+
+    let message: KrpcMessage = round_trip(
+        b"d1:ad2:id20:abcdefghij012345678912:implied_porti1e9:info_hash20:mnopqrstuvwxyz1234564:porti6881e5:token8:aoeusnthe1:q13:announce_peer1:t2:aa1:v4:LT011:y1:qe",
+    );
+    assert_eq!(Some(&b"LT01"[..]), message.version.as_deref());
+    let query = match message.body {
+        KrpcBody::Query(query) => query,
+        body => panic!("expected a query, got {:?}", body),
+    };
+    assert_eq!("announce_peer", query.method());
+    assert_eq!(&ID, query.id());
+    assert_eq!(
+        Query::AnnouncePeer {
+            id: ID,
+            info_hash: OTHER,
+            port: 6881,
+            token: b"aoeusnth".to_vec(),
+            implied_port: true,
+        },
+        query
+    );
+}
+
+#[test]
+fn responses() {
+    let message: KrpcMessage = round_trip(b"d1:rd2:id20:mnopqrstuvwxyz123456e1:t2:aa1:y1:re");
+    assert_eq!(KrpcBody::Response(Response::new(OTHER)), message.body);
+
+    let message: KrpcMessage = round_trip(
+        b"d1:rd2:id20:abcdefghij01234567895:token8:aoeusnth6:valuesl6:axje.u6:idhtnmee1:t2:aa1:y1:re",
+    );
+    let mut response = Response::new(ID);
+    response.token = Some(b"aoeusnth".to_vec());
+    response.values = vec![addr("97.120.106.101:11893"), addr("105.100.104.116:28269")];
+    assert_eq!(KrpcBody::Response(response), message.body);
+
+    let mut response = Response::new(ID);
+    response.nodes = vec![NodeInfo {
+        id: OTHER,
+        addr: addr("10.0.0.1:6881"),
+    }];
+    response.nodes6 = vec![NodeInfo {
+        id: OTHER,
+        addr: addr("[::1]:6882"),
+    }];
+    response.values = vec![addr("[2001:db8::1]:80")];
+    let message = KrpcMessage::new(b"bb".to_vec(), KrpcBody::Response(response));
+
+    let encoded = bencoding::to_canonical_vec(&message).unwrap();
+    assert_eq!(message, bencoding::from_bytes(&encoded).unwrap());
+}
+
+#[test]
+fn errors() {
+    let message: KrpcMessage = round_trip(b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee");
+    assert_eq!(
+        KrpcBody::Error(KrpcError::new(
+            KrpcError::GENERIC,
+            "A Generic Error Ocurred"
+        )),
+        message.body
+    );
+}
+
+#[test]
+fn invalid() {
+    // An unknown method
+    assert!(bencoding::from_bytes::<KrpcMessage>(
+        b"d1:ad2:id20:abcdefghij0123456789e1:q4:pong1:t2:aa1:y1:qe"
+    )
+    .is_err());
+    // A query missing one of its arguments
+    assert!(bencoding::from_bytes::<KrpcMessage>(
+        b"d1:ad2:id20:abcdefghij0123456789e1:q9:find_node1:t2:aa1:y1:qe"
+    )
+    .is_err());
+    // An unknown message type
+    assert!(bencoding::from_bytes::<KrpcMessage>(b"d1:t2:aa1:y1:xe").is_err());
+    // Nodes that aren't a whole number of compact node infos
+    assert!(bencoding::from_bytes::<KrpcMessage>(
+        b"d1:rd2:id20:abcdefghij01234567895:nodes3:abce1:t2:aa1:y1:re"
+    )
+    .is_err());
+}
+
+#[test]
+fn compact_nodes() {
+    let nodes = vec![
+        NodeInfo {
+            id: ID,
+            addr: addr("1.2.3.4:5"),
+        },
+        NodeInfo {
+            id: OTHER,
+            addr: addr("[::1]:6"),
+        },
+    ];
+
+    let v4 = NodeInfo::encode_v4(&nodes);
+    assert_eq!(26, v4.len());
+    assert_eq!(Some(vec![nodes[0]]), NodeInfo::decode_v4(&v4));
+
+    let v6 = NodeInfo::encode_v6(&nodes);
+    assert_eq!(38, v6.len());
+    assert_eq!(Some(vec![nodes[1]]), NodeInfo::decode_v6(&v6));
+}
+
+// The test vectors from BEP 44
+#[test]
+fn storage_targets() {
+    let v = RawBencode::from_value(&"Hello World!").unwrap();
+    assert_eq!(
+        "e5f96f6f38320f0f33959cb4d3d656452117aadb",
+        hex(&immutable_target(&v))
+    );
+
+    let mut k = [0; 32];
+    k.copy_from_slice(&unhex(
+        "77ff84905a91936367c01360803104f92432fcd904a43511876df5cdf3e7e548",
+    ));
+    assert_eq!(
+        "4a533d47ec9c7d95b1ad75f576cffc641853b750",
+        hex(&mutable_target(&k, b""))
+    );
+    assert_eq!(
+        "411eba73b6f087ca51a3795d9c8c938d365e32c1",
+        hex(&mutable_target(&k, b"foobar"))
+    );
+
+    assert_eq!(
+        b"3:seqi1e1:v12:Hello World!",
+        &signature_buffer(b"", 1, &v).unwrap()[..]
+    );
+    assert_eq!(
+        b"4:salt6:foobar3:seqi1e1:v12:Hello World!",
+        &signature_buffer(b"foobar", 1, &v).unwrap()[..]
+    );
+}
+
+#[test]
+fn storage_messages() {
+    let message: KrpcMessage = round_trip(
+        b"d1:ad2:id20:abcdefghij01234567893:seqi4e6:target20:mnopqrstuvwxyz123456e1:q3:get1:t2:aa1:y1:qe",
+    );
+    assert_eq!(
+        KrpcBody::Query(Query::Get {
+            id: ID,
+            target: OTHER,
+            seq: Some(4)
+        }),
+        message.body
+    );
+
+    // The value is kept exactly as it's encoded, even though it's a
+    // dictionary
+    let mut encoded = b"d1:ad3:casi3e2:id20:abcdefghij01234567891:k32:".to_vec();
+    encoded.extend_from_slice(&[7; 32]);
+    encoded.extend_from_slice(b"4:salt3:abc3:seqi4e3:sig64:");
+    encoded.extend_from_slice(&[9; 64]);
+    encoded.extend_from_slice(b"5:token2:tk1:vd1:xli1ei2eeee1:q3:put1:t2:aa1:y1:qe");
+    let message: KrpcMessage = round_trip(&encoded);
+    assert_eq!(
+        KrpcBody::Query(Query::Put {
+            id: ID,
+            token: b"tk".to_vec(),
+            v: RawBencode::from_bytes(b"d1:xli1ei2eee".to_vec()).unwrap(),
+            k: Some([7; 32]),
+            sig: Some([9; 64]),
+            seq: Some(4),
+            cas: Some(3),
+            salt: Some(b"abc".to_vec()),
+        }),
+        message.body
+    );
+
+    let message: KrpcMessage = round_trip(
+        b"d1:rd2:id20:abcdefghij01234567893:seqi2e5:token2:tk1:v12:Hello World!e1:t2:aa1:y1:re",
+    );
+    let mut response = Response::new(ID);
+    response.token = Some(b"tk".to_vec());
+    response.seq = Some(2);
+    response.v = Some(RawBencode::from_value(&"Hello World!").unwrap());
+    assert_eq!(KrpcBody::Response(response), message.body);
+
+    // An immutable put must have a value
+    assert!(bencoding::from_bytes::<KrpcMessage>(
+        b"d1:ad2:id20:abcdefghij01234567895:token2:tke1:q3:put1:t2:aa1:y1:qe"
+    )
+    .is_err());
+}
+
+#[test]
+fn storage_limits() {
+    let long_value = RawBencode::from_value(&"x".repeat(MAX_VALUE_LEN)).unwrap();
+    let value = RawBencode::from_value(&"x".repeat(MAX_VALUE_LEN - 4)).unwrap();
+    let salt = [b's'; MAX_SALT_LEN + 1];
+
+    assert_eq!(MAX_VALUE_LEN, value.as_bytes().len());
+    assert!(signature_buffer(&salt[1..], 1, &value).is_ok());
+    assert_eq!(
+        Err(StorageError::ValueTooLong(MAX_VALUE_LEN + 5)),
+        signature_buffer(b"", 1, &long_value)
+    );
+    assert_eq!(
+        Err(StorageError::SaltTooLong(MAX_SALT_LEN + 1)),
+        signature_buffer(&salt, 1, &value)
+    );
+
+    // A node rejects a put of an item it wouldn't store
+    let put = |salt: &[u8], v: &RawBencode| {
+        let mut encoded = b"d1:ad2:id20:abcdefghij01234567894:salt".to_vec();
+        encoded.extend_from_slice(format!("{}:", salt.len()).as_bytes());
+        encoded.extend_from_slice(salt);
+        encoded.extend_from_slice(b"5:token2:tk1:v");
+        encoded.extend_from_slice(v.as_bytes());
+        encoded.extend_from_slice(b"e1:q3:put1:t2:aa1:y1:qe");
+        bencoding::from_bytes::<KrpcMessage>(&encoded)
+    };
+
+    assert!(put(&salt[1..], &value).is_ok());
+    assert!(put(&salt, &value).is_err());
+    assert!(put(b"", &long_value).is_err());
+}
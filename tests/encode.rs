@@ -78,3 +78,140 @@ fn person_encode() {
         bencoding::to_string(&vstruct).unwrap()
     );
 }
+
+#[test]
+fn encode_bytes() {
+    let bytes = serde_bytes::Bytes::new(&[0x00, 0xff, b'e', 0x80]);
+
+    assert_eq!(
+        b"4:\x00\xffe\x80".to_vec(),
+        bencoding::to_vec(&bytes).unwrap()
+    );
+
+    let bytes = serde_bytes::Bytes::new(&[]);
+
+    assert_eq!(b"0:".to_vec(), bencoding::to_vec(&bytes).unwrap());
+
+    let bytes = serde_bytes::Bytes::new(&[0xff]);
+
+    assert!(bencoding::to_string(&bytes).is_err());
+}
+
+#[test]
+fn encode_to_writer() {
+    let mut output = Vec::new();
+    let vec: Vec<Vec<i64>> = vec![vec![16, 3], vec![12, 25]];
+
+    bencoding::to_writer(&mut output, &vec).unwrap();
+
+    assert_eq!(b"lli16ei3eeli12ei25eee".to_vec(), output);
+
+    let mut serializer = bencoding::ser::Serializer::new(Vec::new());
+
+    serde::Serialize::serialize("spam", &mut serializer).unwrap();
+
+    assert_eq!(b"4:spam".to_vec(), serializer.into_inner());
+}
+
+#[test]
+fn encode_canonical() {
+    use serde::Serializer;
+    use std::collections::HashMap;
+    use structs::Person;
+
+    let person = Person::new("test_01".to_string(), "Male".to_string(), 50);
+
+    assert_eq!(
+        b"d3:agei50e6:gender4:Male4:name7:test_01e".to_vec(),
+        bencoding::to_canonical_vec(&person).unwrap()
+    );
+
+    let mut map = HashMap::new();
+    map.insert("zebra", vec![HashMap::from([("b", 1), ("a", 2)])]);
+    map.insert("ab", Vec::new());
+    map.insert("a", Vec::new());
+
+    assert_eq!(
+        b"d1:ale2:able5:zebrald1:ai2e1:bi1eeee".to_vec(),
+        bencoding::to_canonical_vec(&map).unwrap()
+    );
+
+    let mut serializer = bencoding::ser::Serializer::canonical(Vec::new());
+
+    assert!(matches!(
+        serializer.collect_map(vec![("spam", 1), ("eggs", 2), ("spam", 3)]),
+        Err(bencoding::ser::BencodingSerializeError::DuplicateKey(key)) if key == "spam"
+    ));
+}
+
+#[test]
+fn encode_unsupported() {
+    use bencoding::ser::BencodingSerializeError;
+    use std::collections::HashMap;
+
+    assert!(matches!(
+        bencoding::to_vec(&true),
+        Err(BencodingSerializeError::UnsupportedType("bool"))
+    ));
+    assert!(matches!(
+        bencoding::to_vec(&1.5_f64),
+        Err(BencodingSerializeError::UnsupportedType("f64"))
+    ));
+    assert!(matches!(
+        bencoding::to_vec(&()),
+        Err(BencodingSerializeError::UnsupportedType("unit"))
+    ));
+    assert!(matches!(
+        bencoding::to_vec(&None::<i64>),
+        Err(BencodingSerializeError::UnsupportedType("none"))
+    ));
+    assert!(matches!(
+        bencoding::to_value(&false),
+        Err(BencodingSerializeError::UnsupportedType("bool"))
+    ));
+
+    let map = HashMap::from([(vec![1_i64], 2)]);
+
+    assert!(matches!(
+        bencoding::to_canonical_vec(&map),
+        Err(BencodingSerializeError::NonStringKey)
+    ));
+    assert!(matches!(
+        bencoding::to_value(&map),
+        Err(BencodingSerializeError::NonStringKey)
+    ));
+}
+
+#[test]
+fn encode_map_keys() {
+    use bencoding::ser::BencodingSerializeError;
+    use std::collections::BTreeMap;
+
+    #[derive(serde::Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Event {
+        Started,
+        Stopped,
+    }
+
+    let map = BTreeMap::from([(Event::Stopped, 2), (Event::Started, 1)]);
+
+    assert_eq!(
+        b"d7:Startedi1e7:Stoppedi2ee".to_vec(),
+        bencoding::to_vec(&map).unwrap()
+    );
+
+    let map = BTreeMap::from([('b', 2), ('a', 1)]);
+
+    assert_eq!(b"d1:ai1e1:bi2ee".to_vec(), bencoding::to_vec(&map).unwrap());
+
+    let map = BTreeMap::from([(1_u32, 2), (3, 4)]);
+
+    assert!(matches!(
+        bencoding::to_vec(&map),
+        Err(BencodingSerializeError::NonStringKey)
+    ));
+    assert!(matches!(
+        bencoding::to_value(&map),
+        Err(BencodingSerializeError::NonStringKey)
+    ));
+}
@@ -0,0 +1,103 @@
+use std::io::Cursor;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+enum Shape {
+    Unit,
+    Newtype(i64),
+    Tuple(i64, String),
+    Struct { x: i64, y: String },
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "y")]
+enum Message {
+    #[serde(rename = "q")]
+    Query { q: String, t: String },
+    #[serde(rename = "e")]
+    Error { e: (i64, String), t: String },
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent {
+    Ping,
+    Port(u16),
+}
+
+fn assert_round_trip<T>(value: &T, encoded: &[u8])
+where
+    T: Serialize + for<'de> Deserialize<'de> + PartialEq + std::fmt::Debug,
+{
+    assert_eq!(encoded.to_vec(), bencoding::to_vec(value).unwrap());
+    assert_eq!(*value, bencoding::from_bytes(encoded).unwrap());
+    assert_eq!(
+        *value,
+        bencoding::from_reader(Cursor::new(encoded)).unwrap()
+    );
+
+    let as_value = bencoding::to_value(value).unwrap();
+
+    assert_eq!(as_value, bencoding::from_bytes(encoded).unwrap());
+    assert_eq!(*value, bencoding::from_value(as_value).unwrap());
+}
+
+#[test]
+fn externally_tagged() {
+    assert_round_trip(&Shape::Unit, b"4:Unit");
+    assert_round_trip(&Shape::Newtype(5), b"d7:Newtypei5ee");
+    assert_round_trip(&Shape::Tuple(1, "a".to_string()), b"d5:Tupleli1e1:aee");
+    assert_round_trip(
+        &Shape::Struct {
+            x: 1,
+            y: "b".to_string(),
+        },
+        b"d6:Structd1:xi1e1:y1:bee",
+    );
+    assert_round_trip(
+        &vec![Shape::Unit, Shape::Newtype(-2)],
+        b"l4:Unitd7:Newtypei-2eee",
+    );
+}
+
+#[test]
+fn internally_tagged() {
+    let query = Message::Query {
+        q: "ping".to_string(),
+        t: "aa".to_string(),
+    };
+
+    assert_eq!(
+        b"d1:q4:ping1:t2:aa1:y1:qe".to_vec(),
+        bencoding::to_canonical_vec(&query).unwrap()
+    );
+    assert_eq!(
+        query,
+        bencoding::from_bytes(b"d1:q4:ping1:t2:aa1:y1:qe").unwrap()
+    );
+
+    let error = Message::Error {
+        e: (201, "A Generic Error Ocurred".to_string()),
+        t: "aa".to_string(),
+    };
+
+    assert_eq!(
+        error,
+        bencoding::from_bytes(b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee").unwrap()
+    );
+}
+
+#[test]
+fn adjacently_tagged() {
+    assert_round_trip(&Adjacent::Ping, b"d1:t4:Pinge");
+    assert_round_trip(&Adjacent::Port(6881), b"d1:t4:Port1:ci6881ee");
+}
+
+#[test]
+fn unknown_variant() {
+    assert!(bencoding::from_bytes::<Shape>(b"5:Other").is_err());
+    assert!(bencoding::from_bytes::<Shape>(b"d4:Unit4:Unite").is_ok());
+    assert!(bencoding::from_bytes::<Shape>(b"7:Newtype").is_err());
+    assert!(bencoding::from_bytes::<Shape>(b"d7:Newtypei5e5:Extrai6ee").is_err());
+}
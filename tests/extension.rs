@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use bencoding::de::BencodingDeserializeError;
+use bencoding::from_bytes_with_remainder;
+use bencoding::peer::{ExtendedHandshake, UtMetadata, UT_METADATA};
+
+#[test]
+fn remainder() {
+    let (value, rest) = from_bytes_with_remainder::<u32>(b"i42eabc").unwrap();
+    assert_eq!(42, value);
+    assert_eq!(b"abc", rest);
+
+    let (value, rest) = from_bytes_with_remainder::<String>(b"4:spam").unwrap();
+    assert_eq!("spam", value);
+    assert!(rest.is_empty());
+
+    assert!(bencoding::from_bytes::<u32>(b"i42eabc").is_err());
+    assert!(from_bytes_with_remainder::<u32>(b"i42").is_err());
+}
+
+#[test]
+fn handshake() {
+    let encoded = b"d1:md11:ut_metadatai3e6:ut_pexi0ee13:metadata_sizei31235e1:pi6881e4:reqqi500e1:v13:\xc2\xb5Torrent 1.26:yourip4:\x7f\x00\x00\x01e";
+    let handshake: ExtendedHandshake = bencoding::from_bytes(encoded).unwrap();
+
+    let mut extensions = BTreeMap::new();
+    extensions.insert(UT_METADATA.to_string(), 3);
+    extensions.insert("ut_pex".to_string(), 0);
+    assert_eq!(
+        ExtendedHandshake {
+            extensions,
+            client: Some("\u{b5}Torrent 1.2".to_string()),
+            port: Some(6881),
+            reqq: Some(500),
+            metadata_size: Some(31235),
+            your_ip: Some("127.0.0.1".parse().unwrap()),
+        },
+        handshake
+    );
+    assert_eq!(Some(3), handshake.extension_id(UT_METADATA));
+    // An id of 0 means the extension is disabled
+    assert_eq!(None, handshake.extension_id("ut_pex"));
+    assert_eq!(None, handshake.extension_id("lt_donthave"));
+
+    assert_eq!(
+        &encoded[..],
+        &bencoding::to_canonical_vec(&handshake).unwrap()[..]
+    );
+
+    let handshake = ExtendedHandshake {
+        your_ip: Some("::1".parse().unwrap()),
+        ..ExtendedHandshake::default()
+    };
+    let encoded = bencoding::to_canonical_vec(&handshake).unwrap();
+    assert_eq!(handshake, bencoding::from_bytes(&encoded).unwrap());
+
+    // Every field is optional
+    assert_eq!(
+        ExtendedHandshake::default(),
+        bencoding::from_bytes(b"de").unwrap()
+    );
+    assert!(bencoding::from_bytes::<ExtendedHandshake>(b"d6:yourip3:abce").is_err());
+}
+
+#[test]
+fn ut_metadata() {
+    let request = UtMetadata::decode(b"d8:msg_typei0e5:piecei0ee").unwrap();
+    assert_eq!(UtMetadata::Request { piece: 0 }, request);
+    assert_eq!(b"d8:msg_typei0e5:piecei0ee", &request.encode().unwrap()[..]);
+
+    let data = UtMetadata::decode(b"d8:msg_typei1e5:piecei1e10:total_sizei16390eexxxxxx").unwrap();
+    assert_eq!(
+        UtMetadata::Data {
+            piece: 1,
+            total_size: 16390,
+            data: b"xxxxxx".to_vec(),
+        },
+        data
+    );
+    assert_eq!(1, data.piece());
+    assert_eq!(
+        b"d8:msg_typei1e5:piecei1e10:total_sizei16390eexxxxxx",
+        &data.encode().unwrap()[..]
+    );
+
+    let reject = UtMetadata::decode(b"d8:msg_typei2e5:piecei3ee").unwrap();
+    assert_eq!(UtMetadata::Reject { piece: 3 }, reject);
+    assert_eq!(b"d8:msg_typei2e5:piecei3ee", &reject.encode().unwrap()[..]);
+}
+
+#[test]
+fn invalid_ut_metadata() {
+    // Only data messages have a payload
+    assert!(matches!(
+        UtMetadata::decode(b"d8:msg_typei0e5:piecei0eexx"),
+        Err(BencodingDeserializeError::TrailingCharacters)
+    ));
+    // A data message must say how long the metadata is
+    assert!(UtMetadata::decode(b"d8:msg_typei1e5:piecei0eexx").is_err());
+    // An unknown message type
+    assert!(UtMetadata::decode(b"d8:msg_typei3e5:piecei0ee").is_err());
+    assert!(UtMetadata::decode(b"d8:msg_typei0ee").is_err());
+}
@@ -0,0 +1,22 @@
+// Helpers for tests that work with files on disk, not every test file uses
+// all of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+// Makes an empty directory for a test to write files into, unique to each
+// test
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bencoding-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Makes the contents of a file, which differ for each seed
+pub fn contents(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+        .collect()
+}
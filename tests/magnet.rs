@@ -0,0 +1,127 @@
+use bencoding::torrent::{MagnetError, MagnetLink, Metainfo};
+
+const ALICE_HASH: &str = "b9acdd69d7d8f90a3a082048155fc0c8458e62c7";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn parse_hex() {
+    let link: MagnetLink = format!(
+        "magnet:?xt=urn:btih:{ALICE_HASH}&dn=Alice%27s+Adventures&tr=http%3A%2F%2Ftracker.example.com%3A6969%2Fannounce&tr.1=udp://other.example.com:80&ws=http://seed.example.com/alice.txt&x.pe=10.0.0.1:6881&so=0,2,4-6&unknown=1"
+    )
+    .parse()
+    .unwrap();
+
+    assert_eq!(ALICE_HASH, hex(&link.info_hash_v1.unwrap()));
+    assert_eq!(None, link.info_hash_v2);
+    assert_eq!(Some("Alice's Adventures"), link.name.as_deref());
+    assert_eq!(
+        vec![
+            "http://tracker.example.com:6969/announce",
+            "udp://other.example.com:80"
+        ],
+        link.trackers
+    );
+    assert_eq!(vec!["http://seed.example.com/alice.txt"], link.web_seeds);
+    assert_eq!(vec!["10.0.0.1:6881"], link.peers);
+    assert_eq!(vec![0..=0, 2..=2, 4..=6], link.select_only);
+}
+
+#[test]
+fn parse_base32() {
+    let upper: MagnetLink = "magnet:?xt=urn:btih:XGWN22OX3D4QUOQIEBEBKX6AZBCY4YWH"
+        .parse()
+        .unwrap();
+    let lower: MagnetLink = "magnet:?xt=urn:btih:xgwn22ox3d4quoqiebebkx6azbcy4ywh"
+        .parse()
+        .unwrap();
+
+    assert_eq!(ALICE_HASH, hex(&upper.info_hash_v1.unwrap()));
+    assert_eq!(upper, lower);
+}
+
+#[test]
+fn parse_v2() {
+    let hash = "d0".repeat(32);
+    let link: MagnetLink = format!("magnet:?xt=urn:btmh:1220{hash}&xt=urn:btih:{ALICE_HASH}")
+        .parse()
+        .unwrap();
+
+    assert_eq!(hash, hex(&link.info_hash_v2.unwrap()));
+    assert_eq!(ALICE_HASH, hex(&link.info_hash_v1.unwrap()));
+}
+
+#[test]
+fn parse_invalid() {
+    assert_eq!(
+        Err(MagnetError::NotMagnet),
+        "http://example.com".parse::<MagnetLink>()
+    );
+    assert_eq!(
+        Err(MagnetError::MissingInfoHash),
+        "magnet:?dn=alice".parse::<MagnetLink>()
+    );
+    assert!(matches!(
+        "magnet:?xt=urn:btih:1234".parse::<MagnetLink>(),
+        Err(MagnetError::InvalidInfoHash(_))
+    ));
+    // Every character of a hex info-hash must be a hex digit, with no sign
+    assert!(matches!(
+        format!("magnet:?xt=urn:btih:%2B{}", &ALICE_HASH[1..]).parse::<MagnetLink>(),
+        Err(MagnetError::InvalidInfoHash(_))
+    ));
+    assert!(matches!(
+        format!("magnet:?xt=urn:btmh:1220%2B{}", &"00".repeat(32)[1..]).parse::<MagnetLink>(),
+        Err(MagnetError::InvalidInfoHash(_))
+    ));
+    // A multihash that isn't SHA-256
+    assert!(matches!(
+        format!("magnet:?xt=urn:btmh:1114{}", "00".repeat(20)).parse::<MagnetLink>(),
+        Err(MagnetError::InvalidInfoHash(_))
+    ));
+    assert!(matches!(
+        format!("magnet:?xt=urn:btih:{ALICE_HASH}&dn=%G1").parse::<MagnetLink>(),
+        Err(MagnetError::InvalidEncoding(_))
+    ));
+    assert!(matches!(
+        format!("magnet:?xt=urn:btih:{ALICE_HASH}&so=3-1").parse::<MagnetLink>(),
+        Err(MagnetError::InvalidSelectOnly(_))
+    ));
+}
+
+#[test]
+fn format() {
+    let mut link = MagnetLink::from_info_hash_v1([0xab; 20]);
+    link.info_hash_v2 = Some([0xcd; 32]);
+    link.name = Some("a b&c".to_string());
+    link.trackers = vec!["udp://tracker.example.com:80".to_string()];
+    link.peers = vec!["[::1]:6881".to_string()];
+    link.select_only = vec![1..=1, 3..=5];
+
+    let formatted = link.to_string();
+    assert_eq!(
+        format!(
+            "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}&dn=a%20b%26c&tr=udp%3A%2F%2Ftracker.example.com%3A80&x.pe=%5B%3A%3A1%5D%3A6881&so=1,3-5",
+            "ab".repeat(20),
+            "cd".repeat(32)
+        ),
+        formatted
+    );
+    assert_eq!(link, formatted.parse().unwrap());
+}
+
+#[test]
+fn from_torrent() {
+    let torrent: Metainfo = bencoding::from_bytes(include_bytes!("./alice.torrent")).unwrap();
+    let link = MagnetLink::from(&torrent);
+
+    assert_eq!(ALICE_HASH, hex(&link.info_hash_v1.unwrap()));
+    assert_eq!(None, link.info_hash_v2);
+    assert_eq!(Some(torrent.info().name.as_str()), link.name.as_deref());
+    assert_eq!(
+        vec!["http://tracker.example.com:6969/announce"],
+        link.trackers
+    );
+}
@@ -0,0 +1,21 @@
+// Helpers for tests of network messages, not every test file uses all of them
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+}
+
+// Decodes a message, and checks that it encodes back to the same bytes
+pub fn round_trip<T>(encoded: &[u8]) -> T
+where
+    T: DeserializeOwned + Serialize,
+{
+    let message: T = bencoding::from_bytes(encoded).unwrap();
+    assert_eq!(encoded, &bencoding::to_canonical_vec(&message).unwrap()[..]);
+    message
+}
@@ -0,0 +1,178 @@
+mod files;
+
+use std::fs;
+
+use bencoding::peer::{MetadataAssembler, MetadataError, UtMetadata, METADATA_PIECE_LEN};
+use bencoding::torrent::{Info, MagnetLink, Metainfo, TorrentBuilder, TorrentVersion};
+use bencoding::RawBencode;
+
+// A v1 torrent whose `info` dictionary is split into three pieces
+fn large_torrent() -> Metainfo {
+    let pieces = 2000;
+    let mut info = format!(
+        "d6:lengthi{}e4:name5:a.txt12:piece lengthi16384e6:pieces{}:",
+        pieces * 16384,
+        pieces * 20
+    )
+    .into_bytes();
+    info.extend((0..pieces * 20).map(|i| (i % 251) as u8));
+    info.push(b'e');
+
+    let info: Info = bencoding::from_bytes(&info).unwrap();
+    Metainfo::new(info).unwrap()
+}
+
+fn data(torrent: &Metainfo, piece: u32) -> UtMetadata {
+    let metadata = torrent.info_bytes();
+    let start = piece as usize * METADATA_PIECE_LEN;
+    let end = metadata.len().min(start + METADATA_PIECE_LEN);
+    UtMetadata::Data {
+        piece,
+        total_size: metadata.len() as u64,
+        data: metadata[start..end].to_vec(),
+    }
+}
+
+#[test]
+fn assemble() {
+    let torrent = large_torrent();
+    let size = torrent.info_bytes().len() as u64;
+    let mut assembler = MetadataAssembler::new(&MagnetLink::from(&torrent), size).unwrap();
+
+    assert_eq!(size, assembler.metadata_size());
+    assert_eq!(3, assembler.piece_count());
+    assert_eq!(
+        vec![
+            UtMetadata::Request { piece: 0 },
+            UtMetadata::Request { piece: 1 },
+            UtMetadata::Request { piece: 2 },
+        ],
+        assembler.requests().collect::<Vec<_>>()
+    );
+
+    // Pieces from different peers, out of order and repeated
+    assert!(assembler.receive(&data(&torrent, 2)).unwrap().is_none());
+    assert!(assembler
+        .receive(&UtMetadata::Reject { piece: 0 })
+        .unwrap()
+        .is_none());
+    assert!(assembler.receive(&data(&torrent, 0)).unwrap().is_none());
+    assert!(assembler.receive(&data(&torrent, 2)).unwrap().is_none());
+    assert_eq!(vec![1], assembler.missing_pieces().collect::<Vec<_>>());
+    assert!(!assembler.is_complete());
+
+    let assembled = assembler.receive(&data(&torrent, 1)).unwrap().unwrap();
+    assert_eq!(torrent.info(), assembled.info());
+    assert_eq!(torrent.info_bytes(), assembled.info_bytes());
+    assert!(assembler.is_complete());
+    assert_eq!(0, assembler.requests().count());
+}
+
+#[test]
+fn v2_info_hash() {
+    let dir = files::temp_dir("metadata-v2");
+    let path = dir.join("alice.txt");
+    fs::write(&path, vec![7; 50_000]).unwrap();
+
+    let torrent = TorrentBuilder::new(&path)
+        .version(TorrentVersion::V2)
+        .build()
+        .unwrap();
+    let metadata = torrent.info_bytes();
+    let link = MagnetLink::from_info_hash_v2(torrent.info_hash_v2().unwrap());
+
+    let mut assembler = MetadataAssembler::new(&link, metadata.len() as u64).unwrap();
+    let assembled = assembler.add_piece(0, metadata).unwrap().unwrap();
+    assert_eq!(torrent.info(), assembled.info());
+    assert_eq!(torrent.info_hash_v2(), assembled.info_hash_v2());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn keeps_exact_bytes() {
+    // Unsorted keys and a key this crate doesn't know about, which
+    // re-encoding the decoded `info` would change
+    let metadata = b"d4:name5:a.txt6:lengthi5e7:privatei1e12:piece lengthi16384e6:pieces0:e";
+    let torrent =
+        Metainfo::from_info_bytes(RawBencode::from_bytes(metadata.to_vec()).unwrap()).unwrap();
+    let link = MagnetLink::from(&torrent);
+
+    assert_ne!(
+        bencoding::to_canonical_vec(torrent.info()).unwrap(),
+        metadata.to_vec()
+    );
+
+    let mut assembler = MetadataAssembler::new(&link, metadata.len() as u64).unwrap();
+    let assembled = assembler.add_piece(0, metadata).unwrap().unwrap();
+
+    assert_eq!(&metadata[..], assembled.info_bytes());
+    assert_eq!(link.info_hash_v1, Some(assembled.info_hash_v1()));
+
+    // The bytes are written out unchanged when the torrent is saved
+    let mut encoded = b"d4:info".to_vec();
+    encoded.extend_from_slice(metadata);
+    encoded.push(b'e');
+    assert_eq!(encoded, bencoding::to_vec(&assembled).unwrap());
+}
+
+#[test]
+fn hash_mismatch() {
+    let torrent = large_torrent();
+    let size = torrent.info_bytes().len() as u64;
+    let mut assembler = MetadataAssembler::new(&MagnetLink::from(&torrent), size).unwrap();
+
+    assembler.receive(&data(&torrent, 0)).unwrap();
+    assembler.receive(&data(&torrent, 1)).unwrap();
+    let mut bad = data(&torrent, 2);
+    if let UtMetadata::Data { data, .. } = &mut bad {
+        data[0] ^= 1;
+    }
+    assert!(matches!(
+        assembler.receive(&bad),
+        Err(MetadataError::HashMismatch)
+    ));
+
+    // Every piece is thrown away, and can be fetched again
+    assert_eq!(3, assembler.missing_pieces().count());
+    for piece in 0..2 {
+        assert!(assembler.receive(&data(&torrent, piece)).unwrap().is_none());
+    }
+    assert!(assembler.receive(&data(&torrent, 2)).unwrap().is_some());
+}
+
+#[test]
+fn invalid() {
+    let torrent = large_torrent();
+    let size = torrent.info_bytes().len() as u64;
+    let link = MagnetLink::from(&torrent);
+
+    assert!(matches!(
+        MetadataAssembler::new(&MagnetLink::default(), size),
+        Err(MetadataError::MissingInfoHash)
+    ));
+    assert!(matches!(
+        MetadataAssembler::new(&link, 0),
+        Err(MetadataError::InvalidSize(0))
+    ));
+    assert!(MetadataAssembler::new(&link, 1 << 30).is_err());
+
+    let mut assembler = MetadataAssembler::new(&link, size).unwrap();
+    assert!(matches!(
+        assembler.add_piece(3, b"x"),
+        Err(MetadataError::InvalidPiece(3))
+    ));
+    assert!(matches!(
+        assembler.add_piece(0, b"x"),
+        Err(MetadataError::InvalidPieceLength { piece: 0, len: 1 })
+    ));
+    assert!(matches!(
+        assembler.receive(&UtMetadata::Data {
+            piece: 0,
+            total_size: size + 1,
+            data: vec![0; METADATA_PIECE_LEN],
+        }),
+        Err(MetadataError::SizeMismatch(_))
+    ));
+    assert_eq!(3, assembler.missing_pieces().count());
+}
@@ -0,0 +1,98 @@
+mod messages;
+
+use bencoding::peer::{PexFlags, PexMessage, PexPeer};
+
+use messages::{addr, round_trip};
+
+#[test]
+fn ipv4() {
+    let message: PexMessage = round_trip(
+        b"d5:added12:\x0a\x00\x00\x01\x1a\xe1\xc0\xa8\x01\x02\x1a\xe27:added.f2:\x12\x016:added60:8:added6.f0:7:dropped6:\x7f\x00\x00\x01\x00\x508:dropped60:e",
+    );
+
+    assert_eq!(
+        PexMessage {
+            added: vec![
+                PexPeer::new(addr("10.0.0.1:6881"), PexFlags::SEED | PexFlags::REACHABLE),
+                PexPeer::new(addr("192.168.1.2:6882"), PexFlags::ENCRYPTION),
+            ],
+            dropped: vec![addr("127.0.0.1:80")],
+            ..PexMessage::default()
+        },
+        message
+    );
+    assert!(message.added[0].flags.contains(PexFlags::SEED));
+    assert!(!message.added[0].flags.contains(PexFlags::UTP));
+    assert!(!message.is_empty());
+}
+
+#[test]
+fn ipv6() {
+    let mut encoded = b"d5:added0:7:added.f0:6:added618:".to_vec();
+    encoded.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+    encoded.extend_from_slice(&[0; 11]);
+    encoded.extend_from_slice(&[1, 0x1a, 0xe1]);
+    encoded.extend_from_slice(b"8:added6.f1:\x047:dropped0:8:dropped618:");
+    encoded.extend_from_slice(&[0; 15]);
+    encoded.extend_from_slice(&[1, 0, 0x50]);
+    encoded.push(b'e');
+
+    let message: PexMessage = round_trip(&encoded);
+    assert_eq!(
+        PexMessage {
+            added6: vec![PexPeer::new(addr("[2001:db8::1]:6881"), PexFlags::UTP)],
+            dropped6: vec![addr("[::1]:80")],
+            ..PexMessage::default()
+        },
+        message
+    );
+}
+
+#[test]
+fn encode() {
+    let mut flags = PexFlags::default();
+    flags |= PexFlags::HOLEPUNCH;
+    let message = PexMessage {
+        // The IPv6 peer is in the wrong list, so is skipped
+        added: vec![
+            PexPeer::new(addr("1.2.3.4:5"), flags),
+            PexPeer::new(addr("[::1]:6"), flags),
+        ],
+        ..PexMessage::default()
+    };
+
+    assert_eq!(
+        &b"d5:added6:\x01\x02\x03\x04\x00\x057:added.f1:\x086:added60:8:added6.f0:7:dropped0:8:dropped60:e"[..],
+        &bencoding::to_canonical_vec(&message).unwrap()[..]
+    );
+
+    let empty = PexMessage::default();
+    assert!(empty.is_empty());
+    assert_eq!(
+        &b"d5:added0:7:added.f0:6:added60:8:added6.f0:7:dropped0:8:dropped60:e"[..],
+        &bencoding::to_canonical_vec(&empty).unwrap()[..]
+    );
+}
+
+#[test]
+fn lenient_and_invalid() {
+    // Missing keys and flags are allowed
+    let message: PexMessage =
+        bencoding::from_bytes(b"d5:added6:\x01\x02\x03\x04\x00\x05e").unwrap();
+    assert_eq!(
+        vec![PexPeer::new(addr("1.2.3.4:5"), PexFlags::default())],
+        message.added
+    );
+    assert_eq!(PexMessage::default(), bencoding::from_bytes(b"de").unwrap());
+
+    // Peers that aren't a whole number of compact peers
+    assert!(bencoding::from_bytes::<PexMessage>(b"d5:added5:\x01\x02\x03\x04\x00e").is_err());
+    assert!(
+        bencoding::from_bytes::<PexMessage>(b"d8:dropped67:\x01\x02\x03\x04\x00\x00\x00e").is_err()
+    );
+    // Flags that don't match the peers
+    assert!(bencoding::from_bytes::<PexMessage>(
+        b"d5:added6:\x01\x02\x03\x04\x00\x057:added.f2:\x00\x00e"
+    )
+    .is_err());
+}
@@ -0,0 +1,102 @@
+mod structs;
+
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Read};
+
+use bencoding::de::{BencodingDeserializeError, Deserializer};
+use bencoding::{StreamDeserializer, Value};
+use serde::Deserialize;
+
+// Hands out a single byte per read, like a slow socket
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((&byte, rest)), Some(slot)) => {
+                *slot = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn decode_from_reader() {
+    use structs::Person;
+
+    let torrent = include_bytes!("./alice.torrent");
+    let decoded: Value = bencoding::from_reader(Trickle(torrent)).unwrap();
+
+    assert_eq!(bencoding::from_bytes::<Value>(torrent).unwrap(), decoded);
+
+    let encoded = b"d4:name7:test_016:gender4:Male3:agei50ee";
+    let decoded: Person = bencoding::from_reader(Cursor::new(encoded)).unwrap();
+
+    assert_eq!(
+        Person::new("test_01".to_string(), "Male".to_string(), 50),
+        decoded
+    );
+}
+
+#[test]
+fn reader_stops_after_value() {
+    let mut cursor = Cursor::new(b"l4:spami5eei7e".to_vec());
+    let mut deserializer = Deserializer::from_reader(&mut cursor);
+    let first = Value::deserialize(&mut deserializer).unwrap();
+
+    assert_eq!(Value::List(vec![Value::from("spam"), Value::Int(5)]), first);
+
+    drop(deserializer);
+
+    assert_eq!(11, cursor.position());
+    assert_eq!(7, bencoding::from_reader::<_, i64>(&mut cursor).unwrap());
+}
+
+#[test]
+fn reader_errors() {
+    let error = bencoding::from_reader::<_, i64>(Cursor::new(b"i5ei6e")).unwrap_err();
+
+    assert!(matches!(
+        error.kind(),
+        BencodingDeserializeError::TrailingCharacters
+    ));
+    assert_eq!(Some(3), error.offset());
+
+    let error = bencoding::from_reader::<_, String>(Cursor::new(b"10:spam")).unwrap_err();
+
+    assert!(matches!(
+        error.kind(),
+        BencodingDeserializeError::OutOfCharacters
+    ));
+}
+
+#[test]
+fn end_keeps_next_byte() {
+    let mut deserializer = Deserializer::from_reader(Cursor::new(b"i1ei2e"));
+
+    assert_eq!(1, u32::deserialize(&mut deserializer).unwrap());
+    // Finding out that the input doesn't end reads a byte, which isn't lost
+    assert!(deserializer.end().is_err());
+    assert_eq!(2, u32::deserialize(&mut deserializer).unwrap());
+    deserializer.end().unwrap();
+}
+
+#[test]
+fn stream_from_reader() {
+    let input = b"d1:ai1eed1:ai2eed1:a1:xe";
+    let mut stream =
+        StreamDeserializer::<BTreeMap<String, u32>>::new(Deserializer::from_reader(Trickle(input)));
+
+    assert_eq!(Some(&1), stream.next().unwrap().unwrap().get("a"));
+    assert_eq!(Some(&2), stream.next().unwrap().unwrap().get("a"));
+    assert_eq!(16, stream.end_offset());
+
+    // Offsets count the bytes of the values that have been dropped, and the
+    // path is kept
+    let error = stream.next().unwrap().unwrap_err();
+    assert_eq!(Some("a"), error.path());
+    assert!(error.offset().unwrap() > 16);
+}
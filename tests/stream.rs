@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use bencoding::de::Deserializer;
+use bencoding::{from_bytes_prefix, DecoderOptions, StreamDeserializer};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Entry {
+    id: u32,
+    name: String,
+}
+
+#[test]
+fn prefix() {
+    let (entry, len) = from_bytes_prefix::<Entry>(b"d2:idi1e4:name5:alicee4:rest").unwrap();
+    assert_eq!(
+        Entry {
+            id: 1,
+            name: "alice".to_string()
+        },
+        entry
+    );
+    assert_eq!(22, len);
+
+    let (list, len) = from_bytes_prefix::<Vec<u8>>(b"li1ei2eeli3ee").unwrap();
+    assert_eq!(vec![1, 2], list);
+    assert_eq!(8, len);
+
+    let (value, len) = from_bytes_prefix::<String>(b"4:spam").unwrap();
+    assert_eq!("spam", value);
+    assert_eq!(6, len);
+
+    // Only `from_bytes` rejects what comes after the value
+    assert!(bencoding::from_bytes::<u32>(b"i42eabc").is_err());
+    // The whole value must be there
+    assert!(from_bytes_prefix::<Vec<u8>>(b"li1ei2e").is_err());
+    assert!(from_bytes_prefix::<u32>(b"i42").is_err());
+}
+
+#[test]
+fn offset() {
+    let input = b"4:spami7e";
+    let mut deserializer = Deserializer::from_bytes(input);
+
+    assert_eq!("spam", String::deserialize(&mut deserializer).unwrap());
+    assert_eq!(6, deserializer.offset());
+    assert_eq!(7, u8::deserialize(&mut deserializer).unwrap());
+    assert_eq!(input.len(), deserializer.offset());
+    deserializer.end().unwrap();
+}
+
+#[test]
+fn end_offset() {
+    let input = b"d2:idi1e4:name5:alicee4:rest";
+    let mut deserializer = Deserializer::from_bytes(input);
+
+    Entry::deserialize(&mut deserializer).unwrap();
+    assert_eq!(22, deserializer.end_offset());
+    assert_eq!(deserializer.offset(), deserializer.end_offset());
+    assert!(deserializer.end().is_err());
+}
+
+#[test]
+fn stream() {
+    let input = b"d2:idi1e4:name5:aliceed2:idi2e4:name3:bobe";
+
+    let entries = StreamDeserializer::<Entry>::new(Deserializer::from_bytes(input))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(2, entries.len());
+    assert_eq!("bob", entries[1].name);
+
+    // Values of any type can follow one another
+    let values =
+        StreamDeserializer::<BTreeMap<String, u32>>::new(Deserializer::from_bytes(b"dedeld"));
+    let values: Vec<_> = values.collect();
+    assert_eq!(3, values.len());
+    assert!(values[0].is_ok() && values[1].is_ok() && values[2].is_err());
+
+    // Nothing at all is an empty stream
+    assert_eq!(
+        0,
+        StreamDeserializer::<u32>::new(Deserializer::from_bytes(b"")).count()
+    );
+}
+
+#[test]
+fn stream_error() {
+    let mut stream = StreamDeserializer::<u32>::new(Deserializer::from_bytes(b"i1ei2ex3ei4e"));
+
+    assert_eq!(1, stream.next().unwrap().unwrap());
+    assert_eq!(2, stream.next().unwrap().unwrap());
+    assert_eq!(6, stream.end_offset());
+
+    assert!(stream.next().unwrap().is_err());
+    // Iteration stops after an error, leaving the offset at the last value
+    assert!(stream.next().is_none());
+    assert_eq!(6, stream.end_offset());
+}
+
+#[test]
+fn stream_options() {
+    let deserializer = Deserializer::with_options(b"i1ei01e", DecoderOptions::strict());
+    let values: Vec<_> = StreamDeserializer::<u32>::new(deserializer).collect();
+
+    assert_eq!(1, *values[0].as_ref().unwrap());
+    assert!(values[1].is_err());
+}
+
+// A log file that values were appended to
+#[test]
+fn stream_reader() {
+    let log = b"d2:idi1e4:name5:aliceed2:idi2e4:name3:bobe".to_vec();
+
+    let names = StreamDeserializer::<Entry>::new(Deserializer::from_reader(Cursor::new(log)))
+        .map(|entry| entry.unwrap().name)
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["alice", "bob"], names);
+}
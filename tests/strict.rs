@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use bencoding::de::{BencodingDeserializeError, DEFAULT_MAX_DEPTH};
+use bencoding::{from_bytes_with_options, DecoderOptions, RawBencode, Value};
+
+fn strict<'de, T>(input: &'de [u8]) -> Result<T, BencodingDeserializeError>
+where
+    T: serde::Deserialize<'de>,
+{
+    from_bytes_with_options(input, DecoderOptions::strict())
+        .map_err(BencodingDeserializeError::into_kind)
+}
+
+#[test]
+fn strict_accepts_canonical() {
+    assert_eq!(0, strict::<i64>(b"i0e").unwrap());
+    assert_eq!(-30, strict::<i64>(b"i-30e").unwrap());
+    assert_eq!("", strict::<String>(b"0:").unwrap());
+    assert_eq!("abc", strict::<String>(b"3:abc").unwrap());
+
+    let torrent = include_bytes!("./alice.torrent");
+
+    assert!(strict::<Value>(torrent).is_ok());
+}
+
+#[test]
+fn strict_rejects_integers() {
+    assert!(matches!(
+        strict::<i64>(b"i03e"),
+        Err(BencodingDeserializeError::LeadingZero)
+    ));
+    assert!(matches!(
+        strict::<i64>(b"i-03e"),
+        Err(BencodingDeserializeError::LeadingZero)
+    ));
+    assert!(matches!(
+        strict::<i64>(b"i-0e"),
+        Err(BencodingDeserializeError::NegativeZero)
+    ));
+    assert!(matches!(
+        strict::<i64>(b"ie"),
+        Err(BencodingDeserializeError::EmptyInteger)
+    ));
+    // Digits that may just not have arrived yet aren't missing
+    assert!(matches!(
+        strict::<i64>(b"i-"),
+        Err(BencodingDeserializeError::OutOfCharacters)
+    ));
+    assert!(matches!(
+        strict::<String>(b"03:abc"),
+        Err(BencodingDeserializeError::LeadingZero)
+    ));
+
+    // Outside of strict mode these are still accepted
+    assert_eq!(3, bencoding::from_bytes::<i64>(b"i03e").unwrap());
+    assert_eq!("abc", bencoding::from_bytes::<String>(b"03:abc").unwrap());
+}
+
+#[test]
+fn strict_rejects_keys() {
+    assert!(matches!(
+        strict::<BTreeMap<String, i64>>(b"d1:bi1e1:ai2ee"),
+        Err(BencodingDeserializeError::UnsortedKey(key)) if key == "a"
+    ));
+    assert!(matches!(
+        strict::<BTreeMap<String, i64>>(b"d1:ai1e1:ai2ee"),
+        Err(BencodingDeserializeError::DuplicateKey(key)) if key == "a"
+    ));
+    assert!(matches!(
+        strict::<Value>(b"ld2:abi1e1:bd1:yi1e1:xi2eeee"),
+        Err(BencodingDeserializeError::UnsortedKey(key)) if key == "x"
+    ));
+
+    // Keys compare by their raw bytes, not their length
+    assert!(strict::<Value>(b"d1:ai1e2:abi2e1:bi3ee").is_ok());
+    assert!(bencoding::from_bytes::<Value>(b"d1:bi1e1:ai2ee").is_ok());
+}
+
+#[test]
+fn nesting_limit() {
+    // Deep nesting is an error rather than overflowing the stack, whether or
+    // not decoding is strict
+    let deep = vec![b'l'; 200_000];
+
+    assert!(matches!(
+        strict::<Value>(&deep),
+        Err(BencodingDeserializeError::DepthLimitExceeded(
+            DEFAULT_MAX_DEPTH
+        ))
+    ));
+    assert!(matches!(
+        bencoding::from_bytes::<RawBencode>(&deep).map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::DepthLimitExceeded(
+            DEFAULT_MAX_DEPTH
+        ))
+    ));
+
+    let mut nested = vec![b'l'; DEFAULT_MAX_DEPTH];
+    nested.extend(vec![b'e'; DEFAULT_MAX_DEPTH]);
+    assert!(strict::<Value>(&nested).is_ok());
+
+    // The limit can be changed, and counts dictionaries too
+    let options = DecoderOptions {
+        max_depth: 2,
+        ..DecoderOptions::strict()
+    };
+
+    assert!(from_bytes_with_options::<Value>(b"ld1:ai1eee", options).is_ok());
+    assert!(matches!(
+        from_bytes_with_options::<Value>(b"lld1:ai1eeee", options)
+            .map_err(BencodingDeserializeError::into_kind),
+        Err(BencodingDeserializeError::DepthLimitExceeded(2))
+    ));
+    assert!(
+        from_bytes_with_options::<BTreeMap<String, Vec<Vec<u8>>>>(b"d1:alleee", options).is_err()
+    );
+}
@@ -1,11 +1,12 @@
-// Structs used in testing
+// Structs used in testing, not every test file uses all of them
+#![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub struct Person {
     name: String,
-    // Bencoding supports neither enums or bools
+    // Bencoding has no bools
     gender: String,
     age: u16,
 }
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use bencoding::torrent::{FileEntry, FileTree, FileTreeNode, Info, Metainfo, V2File};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn single_file() {
+    let file = include_bytes!("./alice.torrent");
+    let torrent: Metainfo = bencoding::from_bytes(file).unwrap();
+
+    assert_eq!(
+        Some("http://tracker.example.com:6969/announce"),
+        torrent.announce.as_deref()
+    );
+    assert_eq!(Some("bencoding"), torrent.created_by.as_deref());
+    assert_eq!(Some(1_600_000_000), torrent.creation_date);
+
+    let info = torrent.info();
+
+    assert!(!info.is_multi_file());
+    assert!(!info.is_private());
+    assert_eq!(16384, info.piece_length);
+    assert_eq!(36360, info.total_length());
+    assert_eq!(3, info.piece_count());
+    assert_eq!(3, info.piece_hashes().count());
+
+    assert_eq!(
+        "b9acdd69d7d8f90a3a082048155fc0c8458e62c7",
+        hex(&torrent.info_hash_v1())
+    );
+
+    // Re-encoding keeps the file exactly as it was
+    assert_eq!(file.to_vec(), bencoding::to_vec(&torrent).unwrap());
+}
+
+#[test]
+fn info_bytes_kept_exactly() {
+    // The info dictionary is unsorted and has a key `Info` doesn't know about
+    let encoded = b"d4:infod4:name1:a6:lengthi1e12:piece lengthi1e6:pieces20:aaaaaaaaaaaaaaaaaaaa5:extrai1eee";
+    let torrent: Metainfo = bencoding::from_bytes(encoded).unwrap();
+
+    assert_eq!(&encoded[7..encoded.len() - 1], torrent.info_bytes());
+    assert_eq!(encoded.to_vec(), bencoding::to_vec(&torrent).unwrap());
+}
+
+#[test]
+fn multi_file() {
+    let info = Info {
+        name: "dir".to_string(),
+        piece_length: 32,
+        pieces: Some(vec![0; 40]),
+        length: None,
+        md5sum: None,
+        files: Some(vec![
+            FileEntry {
+                length: 30,
+                path: vec!["a.txt".to_string()],
+                md5sum: None,
+                attr: None,
+            },
+            FileEntry {
+                length: 10,
+                path: vec!["sub".to_string(), "b.txt".to_string()],
+                md5sum: None,
+                attr: None,
+            },
+        ]),
+        private: Some(1),
+        meta_version: None,
+        file_tree: None,
+    };
+    let mut torrent = Metainfo::new(info).unwrap();
+    torrent.announce = Some("udp://tracker.example.com:80".to_string());
+
+    let encoded = bencoding::to_vec(&torrent).unwrap();
+    let decoded: Metainfo = bencoding::from_bytes(&encoded).unwrap();
+
+    assert_eq!(torrent, decoded);
+    assert!(decoded.info().is_multi_file());
+    assert!(decoded.info().is_private());
+    assert_eq!(40, decoded.info().total_length());
+    assert_eq!(torrent.info_hash_v1(), decoded.info_hash_v1());
+
+    // The info dictionary is encoded canonically
+    assert!(decoded
+        .info_bytes()
+        .starts_with(b"d5:filesld6:lengthi30e4:pathl5:a.txteed"));
+}
+
+#[test]
+fn invalid_info() {
+    // Both `length` and `files`
+    let encoded = b"d4:infod5:filesle6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee";
+
+    assert!(bencoding::from_bytes::<Metainfo>(encoded).is_err());
+
+    // A truncated piece hash
+    let encoded = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces3:abcee";
+
+    assert!(bencoding::from_bytes::<Metainfo>(encoded).is_err());
+}
+
+fn v2_tree() -> FileTree {
+    let file = |length, root| {
+        FileTreeNode::File(V2File {
+            length,
+            pieces_root: root,
+        })
+    };
+
+    FileTree(BTreeMap::from([
+        ("big.bin".to_string(), file(40000, Some([1; 32]))),
+        (
+            "sub".to_string(),
+            FileTreeNode::Directory(BTreeMap::from([
+                ("empty".to_string(), file(0, None)),
+                ("small.txt".to_string(), file(10, Some([2; 32]))),
+            ])),
+        ),
+    ]))
+}
+
+#[test]
+fn v2_only() {
+    let info = Info {
+        name: "v2".to_string(),
+        piece_length: 16384,
+        pieces: None,
+        length: None,
+        md5sum: None,
+        files: None,
+        private: None,
+        meta_version: Some(2),
+        file_tree: Some(v2_tree()),
+    };
+    let mut torrent = Metainfo::new(info).unwrap();
+    torrent.piece_layers = Some(BTreeMap::from([([1; 32], vec![3; 96])]));
+
+    let encoded = bencoding::to_vec(&torrent).unwrap();
+    let decoded: Metainfo = bencoding::from_bytes(&encoded).unwrap();
+
+    assert_eq!(torrent, decoded);
+    assert!(decoded.info().is_v2());
+    assert!(!decoded.info().is_v1());
+    assert_eq!(40010, decoded.info().total_length());
+    assert_eq!(Some(&[3; 96][..]), decoded.piece_layer(&[1; 32]));
+    assert_eq!(None, decoded.piece_layer(&[2; 32]));
+
+    let tree = decoded.info().file_tree.as_ref().unwrap();
+    let paths: Vec<_> = tree.files().into_iter().map(|(path, _)| path).collect();
+
+    assert_eq!(
+        vec![
+            vec!["big.bin"],
+            vec!["sub", "empty"],
+            vec!["sub", "small.txt"]
+        ],
+        paths
+    );
+
+    // Files are dictionaries with an empty key, and empty files have no root
+    let needle = b"5:emptyd0:d6:lengthi0eee";
+
+    assert!(decoded
+        .info_bytes()
+        .windows(needle.len())
+        .any(|window| window == needle));
+    assert_eq!(torrent.info_hash_v2(), decoded.info_hash_v2());
+}
+
+#[test]
+fn hybrid() {
+    let info = Info {
+        name: "hybrid".to_string(),
+        piece_length: 16384,
+        pieces: Some(vec![0; 60]),
+        length: None,
+        md5sum: None,
+        files: Some(vec![
+            FileEntry {
+                length: 40000,
+                path: vec!["big.bin".to_string()],
+                md5sum: None,
+                attr: None,
+            },
+            FileEntry {
+                length: 9152,
+                path: vec![".pad".to_string(), "9152".to_string()],
+                md5sum: None,
+                attr: Some("p".to_string()),
+            },
+        ]),
+        private: None,
+        meta_version: Some(2),
+        file_tree: Some(v2_tree()),
+    };
+    let torrent = Metainfo::new(info).unwrap();
+    let encoded = bencoding::to_vec(&torrent).unwrap();
+    let decoded: Metainfo = bencoding::from_bytes(&encoded).unwrap();
+
+    assert!(decoded.info().is_hybrid());
+    assert!(decoded.info().files.as_ref().unwrap()[1].is_padding());
+    assert_eq!(None, decoded.piece_layers);
+
+    let v1 = decoded.info_hash_v1();
+    let v2 = decoded.info_hash_v2().unwrap();
+
+    assert_ne!(v1[..], v2[..20]);
+    assert_eq!(torrent.info_hash_v2(), Some(v2));
+
+    // A v1 torrent has no v2 hash
+    let file = include_bytes!("./alice.torrent");
+    let torrent: Metainfo = bencoding::from_bytes(file).unwrap();
+
+    assert_eq!(None, torrent.info_hash_v2());
+}
+
+#[test]
+fn binary_keys() {
+    // An unknown key that isn't valid UTF-8 is skipped, and piece layers are
+    // keyed by raw hashes
+    let mut encoded =
+        b"d2:\xff\xfei1e4:infod9:file treed1:ad0:d6:lengthi1e11:pieces root32:".to_vec();
+    encoded.extend_from_slice(&[0xee; 32]);
+    encoded.extend_from_slice(
+        b"eee12:meta versioni2e4:name1:a12:piece lengthi16384ee12:piece layersd32:",
+    );
+    encoded.extend_from_slice(&[0xee; 32]);
+    encoded.extend_from_slice(b"0:ee");
+
+    let torrent: Metainfo = bencoding::from_bytes(&encoded).unwrap();
+
+    assert_eq!(Some(&[][..]), torrent.piece_layer(&[0xee; 32]));
+}
+
+#[test]
+fn invalid_v2() {
+    // A meta version without a file tree
+    let encoded = b"d4:infod12:meta versioni2e4:name1:a12:piece lengthi1eee";
+
+    assert!(bencoding::from_bytes::<Metainfo>(encoded).is_err());
+
+    // Neither v1 nor v2
+    let encoded = b"d4:infod4:name1:a12:piece lengthi1eee";
+
+    assert!(bencoding::from_bytes::<Metainfo>(encoded).is_err());
+
+    // A file that is also a directory
+    let encoded = b"d4:infod9:file treed1:ad0:d6:lengthi0ee1:bd0:d6:lengthi0eeeee12:meta versioni2e4:name1:a12:piece lengthi1eee";
+
+    assert!(bencoding::from_bytes::<Metainfo>(encoded).is_err());
+}
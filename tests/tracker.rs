@@ -0,0 +1,230 @@
+mod messages;
+
+use bencoding::tracker::{
+    compact, AnnounceRequest, AnnounceResponse, Event, Peer, PeerAddr, ScrapeResponse, ScrapeStats,
+    TrackerError,
+};
+
+use messages::addr;
+
+#[test]
+fn announce_compact() {
+    let mut encoded =
+        b"d8:completei5e10:incompletei3e8:intervali1800e12:min intervali900e5:peers12:".to_vec();
+    encoded.extend_from_slice(&[10, 0, 0, 1, 0x1a, 0xe1, 192, 168, 1, 2, 0x1a, 0xe2]);
+    encoded.extend_from_slice(b"6:peers618:");
+    encoded.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    encoded.extend_from_slice(&[0x1a, 0xe1]);
+    encoded.extend_from_slice(b"10:tracker id3:abce");
+
+    let response: AnnounceResponse = bencoding::from_bytes(&encoded).unwrap();
+
+    assert_eq!(None, response.failure_reason);
+    assert_eq!(Some(1800), response.interval);
+    assert_eq!(Some(900), response.min_interval);
+    assert_eq!(Some(5), response.complete);
+    assert_eq!(Some(3), response.incomplete);
+    assert_eq!(Some("abc"), response.tracker_id.as_deref());
+    assert_eq!(
+        vec![
+            Peer::from(addr("10.0.0.1:6881")),
+            Peer::from(addr("192.168.1.2:6882"))
+        ],
+        response.peers
+    );
+    assert_eq!(vec![addr("[2001:db8::1]:6881")], response.peers6);
+    assert_eq!(3, response.addrs().count());
+
+    assert_eq!(encoded, bencoding::to_canonical_vec(&response).unwrap());
+}
+
+#[test]
+fn announce_dictionaries() {
+    let encoded = b"d8:intervali60e5:peersld2:ip8:10.0.0.17:peer id20:aaaaaaaaaaaaaaaaaaaa4:porti6881eed2:ip3:::14:porti51413eeee";
+    let response: AnnounceResponse = bencoding::from_bytes(encoded).unwrap();
+
+    assert_eq!(
+        vec![
+            Peer {
+                id: Some([b'a'; 20]),
+                addr: PeerAddr::Ip(addr("10.0.0.1:6881")),
+            },
+            Peer::from(addr("[::1]:51413")),
+        ],
+        response.peers
+    );
+
+    // Peers with ids can't be compact, so they stay as dictionaries
+    assert_eq!(
+        &encoded[..],
+        &bencoding::to_canonical_vec(&response).unwrap()[..]
+    );
+
+    let bad = b"d5:peersld2:ip0:4:porti1eeee";
+    assert!(bencoding::from_bytes::<AnnounceResponse>(bad).is_err());
+}
+
+#[test]
+fn announce_host_names() {
+    // BEP 3 allows a DNS name in place of an IP address
+    let encoded = b"d5:peersld2:ip11:example.com4:porti6881eed2:ip8:10.0.0.14:porti6882eeee";
+    let response: AnnounceResponse = bencoding::from_bytes(encoded).unwrap();
+
+    assert_eq!(
+        vec![
+            Peer {
+                id: None,
+                addr: PeerAddr::Host("example.com".to_string(), 6881),
+            },
+            Peer::from(addr("10.0.0.1:6882")),
+        ],
+        response.peers
+    );
+    assert_eq!(
+        vec![addr("10.0.0.1:6882")],
+        response.addrs().collect::<Vec<_>>()
+    );
+
+    // Host names can't be compact, so every peer stays a dictionary
+    assert_eq!(
+        &encoded[..],
+        &bencoding::to_canonical_vec(&response).unwrap()[..]
+    );
+}
+
+#[test]
+fn announce_invalid_compact() {
+    // Compact peers must be a multiple of 6 bytes long
+    assert!(bencoding::from_bytes::<AnnounceResponse>(b"d5:peers5:abcdee").is_err());
+    assert!(bencoding::from_bytes::<AnnounceResponse>(b"d6:peers66:abcdefe").is_err());
+}
+
+#[test]
+fn announce_failure() {
+    let response: AnnounceResponse =
+        bencoding::from_bytes(b"d14:failure reason12:unregisterede").unwrap();
+
+    assert_eq!(Some("unregistered"), response.failure_reason.as_deref());
+    assert!(response.peers.is_empty());
+    assert_eq!(None, response.interval);
+}
+
+#[test]
+fn scrape() {
+    let mut encoded = b"d5:filesd20:".to_vec();
+    encoded.extend_from_slice(&[0xff; 20]);
+    encoded.extend_from_slice(b"d8:completei4e10:downloadedi10e10:incompletei2e4:name5:alicee");
+    encoded.extend_from_slice(b"ee");
+
+    let response: ScrapeResponse = bencoding::from_bytes(&encoded).unwrap();
+
+    assert_eq!(
+        Some(&ScrapeStats {
+            complete: 4,
+            downloaded: 10,
+            incomplete: 2,
+            name: Some("alice".to_string()),
+        }),
+        response.files.get(&[0xff; 20])
+    );
+    assert_eq!(encoded, bencoding::to_canonical_vec(&response).unwrap());
+}
+
+#[test]
+fn compact_peers() {
+    let peers = vec![addr("1.2.3.4:5"), addr("[::2]:7")];
+
+    let v4 = compact::encode_v4(&peers);
+    assert_eq!(vec![1, 2, 3, 4, 0, 5], v4);
+    assert_eq!(Some(vec![peers[0]]), compact::decode_v4(&v4));
+
+    let v6 = compact::encode_v6(&peers);
+    assert_eq!(compact::V6_LEN, v6.len());
+    assert_eq!(Some(vec![peers[1]]), compact::decode_v6(&v6));
+
+    assert_eq!(None, compact::decode_v4(&[0; 7]));
+}
+
+#[test]
+fn announce_request() {
+    let mut info_hash = [0; 20];
+    info_hash[..4].copy_from_slice(&[0x12, 0x34, b'a', b' ']);
+    let mut request = AnnounceRequest::new(info_hash, *b"-BE0001-abcdefghijkl", 6881);
+    request.uploaded = 10;
+    request.downloaded = 20;
+    request.left = 30;
+    request.event = Some(Event::Completed);
+    request.numwant = Some(50);
+    request.key = Some("k/1".to_string());
+    request.tracker_id = Some("abc".to_string());
+
+    let query = request.to_query_string();
+    assert_eq!(
+        format!(
+            "info_hash=%124a%20{}&peer_id=-BE0001-abcdefghijkl&port=6881&uploaded=10&downloaded=20&left=30&event=completed&compact=1&numwant=50&key=k%2F1&trackerid=abc",
+            "%00".repeat(16)
+        ),
+        query
+    );
+    assert_eq!(request, query.parse().unwrap());
+
+    assert_eq!(
+        format!("http://tracker.example.com/announce?{query}"),
+        request.url("http://tracker.example.com/announce")
+    );
+    assert_eq!(
+        format!("http://tracker.example.com/announce?passkey=x&{query}"),
+        request.url("http://tracker.example.com/announce?passkey=x")
+    );
+}
+
+#[test]
+fn announce_request_parse() {
+    let query = format!(
+        "?peer_id=-BE0001-abcdefghijkl&info_hash={}&port=1&uploaded=0&downloaded=0&left=0&event=empty&no_peer_id=1",
+        "%ff".repeat(20)
+    );
+    let request: AnnounceRequest = query.parse().unwrap();
+
+    assert_eq!([0xff; 20], request.info_hash);
+    assert_eq!(None, request.event);
+    assert_eq!(None, request.compact);
+
+    assert_eq!(
+        Err(TrackerError::MissingParameter("info_hash")),
+        "port=1".parse::<AnnounceRequest>()
+    );
+    assert_eq!(
+        Err(TrackerError::InvalidParameter("info_hash".to_string())),
+        "info_hash=abc".parse::<AnnounceRequest>()
+    );
+    assert_eq!(
+        Err(TrackerError::InvalidParameter("port".to_string())),
+        "port=70000".parse::<AnnounceRequest>()
+    );
+    assert_eq!(
+        Err(TrackerError::InvalidEncoding("key".to_string())),
+        "key=%zz".parse::<AnnounceRequest>()
+    );
+    assert_eq!(
+        Err(TrackerError::InvalidEncoding("key".to_string())),
+        "key=%+1".parse::<AnnounceRequest>()
+    );
+}
+
+#[test]
+fn announce_request_plus() {
+    // A `+` left unescaped in a binary parameter is the byte itself, not a
+    // space as it is in text
+    let query = format!(
+        "info_hash=+{}&peer_id=-BE0001-abcdefghij+%2B&port=1&uploaded=0&downloaded=0&left=0&key=a+b",
+        "%20".repeat(19)
+    );
+    let request: AnnounceRequest = query.parse().unwrap();
+
+    let mut info_hash = [b' '; 20];
+    info_hash[0] = b'+';
+    assert_eq!(info_hash, request.info_hash);
+    assert_eq!(*b"-BE0001-abcdefghij++", request.peer_id);
+    assert_eq!(Some("a b".to_string()), request.key);
+}
@@ -0,0 +1,213 @@
+mod structs;
+
+use std::collections::BTreeMap;
+
+use bencoding::Value;
+
+#[test]
+fn decode_value() {
+    let decoded: Value = bencoding::from_bytes(b"i-52e").unwrap();
+
+    assert_eq!(Value::Int(-52), decoded);
+
+    let decoded: Value = bencoding::from_bytes(b"3:\xff\x00e").unwrap();
+
+    assert_eq!(Value::Bytes(vec![0xff, 0x00, b'e']), decoded);
+    assert_eq!(None, decoded.as_str());
+
+    let decoded: Value = bencoding::from_bytes(b"l4:spami7ee").unwrap();
+
+    assert_eq!(
+        Value::List(vec![Value::from("spam"), Value::Int(7)]),
+        decoded
+    );
+
+    let decoded: Value = bencoding::from_bytes(b"d3:cow3:moo4:spaml1:a1:bee").unwrap();
+    let mut dict = BTreeMap::new();
+    dict.insert(b"cow".to_vec(), Value::from("moo"));
+    dict.insert(
+        b"spam".to_vec(),
+        Value::List(vec![Value::from("a"), Value::from("b")]),
+    );
+
+    assert_eq!(Value::Dict(dict), decoded);
+}
+
+#[test]
+fn index_value() {
+    let decoded: Value = bencoding::from_bytes(b"d3:cow3:moo4:spaml1:ai5eee").unwrap();
+
+    assert_eq!(Some("moo"), decoded["cow"].as_str());
+    assert_eq!(Some(5), decoded["spam"][1].as_int());
+    assert_eq!(Some(b"a".as_ref()), decoded[&b"spam"[..]][0].as_bytes());
+    assert_eq!(None, decoded.get("missing"));
+    assert_eq!(None, decoded.get(0));
+    assert_eq!(None, decoded["spam"].get(2));
+    assert!(decoded.is_dict());
+    assert!(decoded["spam"].is_list());
+}
+
+#[test]
+fn value_round_trip() {
+    let torrent = include_bytes!("./alice.torrent");
+    let decoded: Value = bencoding::from_bytes(torrent).unwrap();
+
+    assert_eq!(Some("alice.txt"), decoded["info"]["name"].as_str());
+    assert_eq!(Some(16384), decoded["info"]["piece length"].as_int());
+    assert_eq!(
+        Some(60),
+        decoded["info"]["pieces"].as_bytes().map(<[u8]>::len)
+    );
+    assert_eq!(torrent.to_vec(), bencoding::to_vec(&decoded).unwrap());
+}
+
+#[test]
+fn to_and_from_value() {
+    use structs::Person;
+
+    let person = Person::new("test_01".to_string(), "Male".to_string(), 50);
+    let value = bencoding::to_value(&person).unwrap();
+
+    assert_eq!(Some("test_01"), value["name"].as_str());
+    assert_eq!(Some(50), value["age"].as_int());
+
+    let decoded: Person = bencoding::from_value(value).unwrap();
+
+    assert_eq!(person, decoded);
+
+    let vec: Vec<u16> = bencoding::from_value(Value::List(vec![Value::Int(1)])).unwrap();
+
+    assert_eq!(vec![1], vec);
+    assert!(bencoding::from_value::<u8>(Value::Int(256)).is_err());
+}
+
+#[test]
+fn decode_value_ref() {
+    use bencoding::ValueRef;
+
+    let torrent = include_bytes!("./alice.torrent");
+    let decoded: ValueRef = bencoding::from_bytes(torrent).unwrap();
+    let pieces = decoded["info"]["pieces"].as_bytes().unwrap();
+
+    // The byte string points into the input rather than into a copy
+    assert!(torrent.as_ptr_range().contains(&pieces.as_ptr()));
+    assert_eq!(Some("alice.txt"), decoded["info"]["name"].as_str());
+    assert_eq!(torrent.to_vec(), bencoding::to_vec(&decoded).unwrap());
+
+    let owned: Value = bencoding::from_bytes(torrent).unwrap();
+
+    assert_eq!(owned, decoded.into_owned());
+
+    let decoded: ValueRef = bencoding::from_bytes(b"ld1:\xffi1eee").unwrap();
+
+    assert_eq!(Some(1), decoded[0][&b"\xff"[..]].as_int());
+}
+
+#[test]
+fn raw_bencode() {
+    use bencoding::RawBencode;
+
+    // Unsorted keys are kept as they are
+    let encoded = b"d3:rawd1:bi1e1:ai2ee4:spami3ee";
+    let map: BTreeMap<String, RawBencode> = bencoding::from_bytes(encoded).unwrap();
+
+    assert_eq!(b"d1:bi1e1:ai2ee", map["raw"].as_bytes());
+    assert_eq!(b"i3e", map["spam"].as_bytes());
+    assert_eq!(encoded.to_vec(), bencoding::to_vec(&map).unwrap());
+    assert_eq!(encoded.to_vec(), bencoding::to_canonical_vec(&map).unwrap());
+
+    let reader_map: BTreeMap<String, RawBencode> = bencoding::from_reader(&encoded[..]).unwrap();
+
+    assert_eq!(map, reader_map);
+
+    // Going through a `Value` decodes it, sorting the keys
+    let value = bencoding::to_value(&map).unwrap();
+
+    assert_eq!(Some(2), value["raw"]["a"].as_int());
+
+    let raw: RawBencode = bencoding::from_value(value["raw"].clone()).unwrap();
+
+    assert_eq!(b"d1:ai2e1:bi1ee", raw.as_bytes());
+    assert_eq!(
+        BTreeMap::from([("a".to_string(), 2), ("b".to_string(), 1)]),
+        raw.decode::<BTreeMap<String, i64>>().unwrap()
+    );
+
+    assert!(RawBencode::from_bytes(b"i1ei2e".to_vec()).is_err());
+}
+
+#[test]
+fn none_fields_in_value() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Optional {
+        a: Option<u8>,
+        b: Option<u8>,
+        c: Vec<Option<u8>>,
+    }
+
+    let optional = Optional {
+        a: Some(1),
+        b: None,
+        c: Vec::new(),
+    };
+    let value = bencoding::to_value(&optional).unwrap();
+
+    // `None` fields are left out, as they are when encoding to bytes
+    assert_eq!(
+        bencoding::to_vec(&optional).unwrap(),
+        bencoding::to_vec(&value).unwrap()
+    );
+    assert_eq!(None, value.get("b"));
+
+    let map = BTreeMap::from([("a", None), ("b", Some(2))]);
+
+    assert_eq!(
+        b"d1:bi2ee".to_vec(),
+        bencoding::to_vec(&bencoding::to_value(&map).unwrap()).unwrap()
+    );
+
+    // `None` anywhere else still can't be represented
+    let optional = Optional {
+        c: vec![None],
+        ..optional
+    };
+
+    assert!(bencoding::to_value(&optional).is_err());
+}
+
+#[test]
+fn duplicate_keys_in_value() {
+    use bencoding::de::BencodingDeserializeError;
+    use bencoding::ser::BencodingSerializeError;
+    use serde::Serializer;
+
+    // The earlier entry isn't silently overwritten by the later one
+    let error = bencoding::from_bytes::<Value>(b"d1:ai1e1:ai2ee").unwrap_err();
+    assert_eq!(
+        BencodingDeserializeError::DuplicateKey("a".to_string()).to_string(),
+        error.into_kind().to_string()
+    );
+
+    assert!(matches!(
+        bencoding::value::Serializer::new().collect_map(vec![("a", 1), ("a", 2)]),
+        Err(BencodingSerializeError::DuplicateKey(key)) if key == "a"
+    ));
+}
+
+#[test]
+fn duplicate_keys_in_value_ref() {
+    use bencoding::de::BencodingDeserializeError;
+    use bencoding::ValueRef;
+
+    let error = bencoding::from_bytes::<ValueRef>(b"d1:ai1e1:ai2ee").unwrap_err();
+    assert_eq!(
+        BencodingDeserializeError::DuplicateKey("a".to_string()).to_string(),
+        error.into_kind().to_string()
+    );
+
+    // Keys only have to be unique within their own dictionary
+    let decoded: ValueRef = bencoding::from_bytes(b"d1:ad1:ai1eee").unwrap();
+    assert_eq!(Some(1), decoded["a"]["a"].as_int());
+}
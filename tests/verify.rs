@@ -0,0 +1,157 @@
+mod files;
+
+use std::fs;
+use std::path::Path;
+
+use bencoding::torrent::{Metainfo, Status, TorrentBuilder, TorrentError, TorrentVersion};
+
+use files::{contents, temp_dir};
+
+const BLOCK: usize = 16 * 1024;
+
+// Writes three files, where the pieces of the first and second overlap
+fn multi_file(dir: &Path, version: TorrentVersion) -> Metainfo {
+    let root = dir.join("data");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("a"), contents(BLOCK + 100, 1)).unwrap();
+    fs::write(root.join("sub").join("b"), contents(3 * BLOCK, 2)).unwrap();
+    fs::write(root.join("sub").join("c"), contents(50, 3)).unwrap();
+
+    TorrentBuilder::new(&root)
+        .version(version)
+        .piece_length(BLOCK as u64)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn v1_complete() {
+    let dir = temp_dir("v1-complete");
+    let torrent = multi_file(&dir, TorrentVersion::V1);
+
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(5, verification.pieces().len());
+    assert!(verification.is_complete());
+    assert_eq!(vec![0xf8], verification.bitfield());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn v1_bad_and_missing() {
+    let dir = temp_dir("v1-bad");
+    let torrent = multi_file(&dir, TorrentVersion::V1);
+    let root = dir.join("data");
+
+    // Changing a byte in the second piece of `a` also spoils the piece it
+    // shares with `b`, and truncating `b` makes its last pieces missing
+    let mut a = fs::read(root.join("a")).unwrap();
+    a[BLOCK + 1] ^= 0xff;
+    fs::write(root.join("a"), a).unwrap();
+    fs::write(root.join("sub").join("b"), contents(2 * BLOCK, 2)).unwrap();
+
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(
+        &[
+            Status::Good,
+            Status::Bad,
+            Status::Good,
+            Status::Missing,
+            Status::Missing
+        ],
+        verification.pieces()
+    );
+    assert_eq!(2, verification.count(Status::Missing));
+    assert_eq!(vec![0xa0], verification.bitfield());
+
+    fs::remove_dir_all(root.join("sub")).unwrap();
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(4, verification.count(Status::Missing));
+    assert!(!verification.is_complete());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn single_file() {
+    let dir = temp_dir("single");
+    let path = dir.join("file.bin");
+    fs::write(&path, contents(2 * BLOCK + 1, 4)).unwrap();
+    let torrent = TorrentBuilder::new(&path)
+        .version(TorrentVersion::Hybrid)
+        .piece_length(BLOCK as u64)
+        .build()
+        .unwrap();
+
+    assert!(torrent.verify(&dir).unwrap().is_complete());
+    assert_eq!(
+        vec![(vec!["file.bin".to_string()], Status::Good)],
+        torrent.verify_files(&dir).unwrap()
+    );
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(3, torrent.verify(&dir).unwrap().count(Status::Missing));
+    assert_eq!(
+        vec![(vec!["file.bin".to_string()], Status::Missing)],
+        torrent.verify_files(&dir).unwrap()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn v2_pieces() {
+    let dir = temp_dir("v2");
+    let torrent = multi_file(&dir, TorrentVersion::V2);
+    let root = dir.join("data");
+
+    // v2 pieces never span files, so there is one more than in v1
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(6, verification.pieces().len());
+    assert!(verification.is_complete());
+
+    let mut b = fs::read(root.join("sub").join("b")).unwrap();
+    b[2 * BLOCK] ^= 0xff;
+    fs::write(root.join("sub").join("b"), b).unwrap();
+
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(Status::Bad, verification.pieces()[4]);
+    assert_eq!(5, verification.count(Status::Good));
+
+    let files = torrent.verify_files(&dir).unwrap();
+    let statuses: Vec<_> = files.iter().map(|(_, status)| *status).collect();
+    assert_eq!(vec![Status::Good, Status::Bad, Status::Good], statuses);
+    assert_eq!(vec!["sub", "b"], files[1].0);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn hybrid_padding() {
+    let dir = temp_dir("hybrid");
+    let torrent = multi_file(&dir, TorrentVersion::Hybrid);
+
+    // Padding files aren't on disk, but still count towards the pieces
+    let verification = torrent.verify(&dir).unwrap();
+    assert_eq!(6, verification.pieces().len());
+    assert!(verification.is_complete());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unsafe_path() {
+    let dir = temp_dir("unsafe");
+    let torrent = multi_file(&dir, TorrentVersion::V1);
+
+    let mut info = torrent.info().clone();
+    info.files.as_mut().unwrap()[0].path = vec!["..".to_string(), "escape".to_string()];
+    let torrent = Metainfo::new(info).unwrap();
+
+    assert!(matches!(
+        torrent.verify(&dir),
+        Err(TorrentError::UnsafePath(_))
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}